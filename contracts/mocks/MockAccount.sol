@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./MockERC20.sol";
+import "./MockSignatures.sol";
+
+/// A subset of the Account contract that validates role signatures in the
+/// same way, backed by simple storage.
+contract MockAccount {
+    uint8 internal constant ROLE_OWNER = 1;
+    uint8 internal constant ROLE_DEPOSIT = 4;
+    uint8 internal constant ROLE_OPEN = 5;
+
+    bytes32 public constant MESSAGE_SCOPE = keccak256("HANDLE_SYNTH_ACCOUNT_MESSAGE");
+
+    address public treasury;
+    uint256 public mintCounter;
+    mapping(address => uint256) public userNonce;
+    mapping(uint256 => mapping(address => mapping(uint8 => bool))) internal roles;
+    mapping(uint256 => mapping(address => uint256)) internal roleCounts;
+
+    constructor(address _treasury) {
+        treasury = _treasury;
+    }
+
+    function getAccountUser(uint256 id, address user) external view returns (uint256 roleCount) {
+        return roleCounts[id][user];
+    }
+
+    function doesUserHaveAccountRole(uint256 id, address user, uint8 role) public view returns (bool) {
+        return roles[id][user][role] || (role != ROLE_OWNER && roles[id][user][ROLE_OWNER]);
+    }
+
+    function open(
+        uint256 depositAmount,
+        address depositor,
+        address recipient,
+        address liquidToken,
+        bytes calldata openSignature,
+        bytes32,
+        bool useAllowance
+    ) external {
+        _consumeSignature(0, depositor, ROLE_OPEN, openSignature);
+        uint256 id = ++mintCounter;
+        _setRole(id, recipient, ROLE_OWNER, true);
+        _transferDeposit(depositAmount, depositor, liquidToken, useAllowance);
+    }
+
+    function deposit(
+        uint256 id,
+        uint256 amount,
+        address depositor,
+        address liquidToken,
+        bool useAllowance,
+        bytes calldata depositorSignature
+    ) external {
+        require(id > 0 && id <= mintCounter, "account does not exist");
+        _consumeSignature(id, depositor, ROLE_DEPOSIT, depositorSignature);
+        _transferDeposit(amount, depositor, liquidToken, useAllowance);
+    }
+
+    function grantAccountUserRole(
+        uint256 id,
+        address user,
+        uint8 role,
+        address owner,
+        bytes calldata ownerSignature
+    ) external {
+        require(roles[id][owner][ROLE_OWNER], "not owner");
+        _consumeSignature(id, owner, ROLE_OWNER, ownerSignature);
+        _setRole(id, user, role, true);
+    }
+
+    function revokeAccountUserRole(
+        uint256 id,
+        address user,
+        uint8 role,
+        address owner,
+        bytes calldata ownerSignature
+    ) external {
+        require(roles[id][owner][ROLE_OWNER], "not owner");
+        _consumeSignature(id, owner, ROLE_OWNER, ownerSignature);
+        _setRole(id, user, role, false);
+    }
+
+    function _setRole(uint256 id, address user, uint8 role, bool value) internal {
+        if (roles[id][user][role] == value) {
+            return;
+        }
+        roles[id][user][role] = value;
+        if (value) {
+            roleCounts[id][user]++;
+        } else {
+            roleCounts[id][user]--;
+        }
+    }
+
+    function _consumeSignature(uint256 id, address user, uint8 role, bytes calldata signature) internal {
+        bytes32 hash = keccak256(abi.encode(MESSAGE_SCOPE, userNonce[user], id, role));
+        require(MockSignatures.recover(hash, signature) == user, "invalid signature");
+        userNonce[user]++;
+    }
+
+    function _transferDeposit(uint256 amount, address depositor, address token, bool useAllowance) internal {
+        if (useAllowance) {
+            MockERC20(token).transferFrom(depositor, treasury, amount);
+        }
+    }
+}
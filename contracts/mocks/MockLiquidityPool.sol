@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./MockSignatures.sol";
+
+/// A subset of the LiquidityPool contract with pools opened directly.
+/// Pool role signatures are validated with the layout the client assumes,
+/// so tests against this mock check the client's use of the contract's
+/// message scope and nonces, but not the layout itself.
+contract MockLiquidityPool {
+    struct PoolData {
+        bool isOpen;
+        address underlyingToken;
+        address liquidityToken;
+        address owner;
+    }
+
+    uint8 internal constant ROLE_OWNER = 1;
+
+    bytes32 public immutable MESSAGE_SCOPE;
+
+    address public treasury;
+    uint256 public totalSupply;
+    mapping(address => uint256) public userNonce;
+    mapping(address => uint256) public balanceOf;
+    mapping(uint256 => PoolData) internal pools;
+
+    constructor(address _treasury, bytes32 messageScope) {
+        treasury = _treasury;
+        MESSAGE_SCOPE = messageScope;
+    }
+
+    function getPoolData(uint256 poolId) external view returns (PoolData memory) {
+        return pools[poolId];
+    }
+
+    function openPool(uint256 poolId, address underlyingToken, address liquidityToken, address owner) external {
+        require(!pools[poolId].isOpen, "pool already open");
+        pools[poolId] = PoolData(true, underlyingToken, liquidityToken, owner);
+        totalSupply++;
+        balanceOf[owner]++;
+    }
+
+    function validatePoolRoleSignature(
+        uint256 id,
+        address poolUser,
+        uint8 role,
+        bytes calldata payload,
+        bytes calldata signature
+    ) public view {
+        require(role == ROLE_OWNER && pools[id].owner == poolUser, "missing pool role");
+        bytes32 hash = keccak256(abi.encode(MESSAGE_SCOPE, userNonce[poolUser], id, role, payload));
+        require(MockSignatures.recover(hash, signature) == poolUser, "invalid signature");
+    }
+
+    function consumePoolRoleSignature(
+        uint256 id,
+        address poolUser,
+        uint8 role,
+        bytes calldata payload,
+        bytes calldata signature
+    ) external {
+        validatePoolRoleSignature(id, poolUser, role, payload, signature);
+        userNonce[poolUser]++;
+    }
+}
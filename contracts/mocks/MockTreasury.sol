@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/// Holds deposits for the mock account and liquidity pool contracts.
+contract MockTreasury {
+    address public account;
+    address public liquidityPool;
+
+    function initialize(address _account, address _liquidityPool) external {
+        require(account == address(0), "already initialized");
+        account = _account;
+        liquidityPool = _liquidityPool;
+    }
+}
@@ -0,0 +1,572 @@
+//! Command-line interface for routine account and trading operations.
+//! Results are printed as JSON, and subscriptions as JSON lines.
+#[cfg(feature = "interface-only")]
+fn main() {
+    eprintln!("handle-synths is not available with the interface-only feature");
+}
+
+#[cfg(not(feature = "interface-only"))]
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    env_logger::init();
+    cli::run(clap::Parser::parse()).await
+}
+
+#[cfg(not(feature = "interface-only"))]
+mod cli {
+    use bigdecimal::BigDecimal;
+    use clap::{Args, Parser, Subcommand, ValueEnum};
+    use ethers::prelude::{Address, Bytes, LocalWallet, Middleware};
+    use eyre::eyre;
+    use futures::StreamExt;
+    use handle_synths_client::client_connection::ClientConnection;
+    use handle_synths_client::environment::{get_network_config_by_chain_id, set_config, Config};
+    use handle_synths_client::interface::liquidity_pool::LiquidityPoolId;
+    use handle_synths_client::interface::order::{
+        LimitOrderArgs, LimitTriggerOrderArgs, OrderKind, StopLimitOrderArgs, StopMarketOrderArgs,
+    };
+    use handle_synths_client::interface::pair::Pair;
+    use handle_synths_client::interface::requests::TradeSize;
+    use handle_synths_client::interface::system_params::SystemParamRegistry;
+    use handle_synths_client::interface::{AccountId, AccountRole, SubscriptionTopic};
+    use handle_synths_client::keys::{decrypt_keystore, KeystorePassword};
+    use handle_synths_client::protocol_admin::ProtocolAdminClient;
+    use handle_synths_client::trade_account::TradeAccountClient;
+    use handle_synths_client::trade_account_state::TradeAccountStateChange;
+    use handle_synths_client::user::User;
+    use serde::Serialize;
+    use std::env;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    const KEYSTORE_PASSWORD_ENV: &str = "HANDLE_SYNTHS_KEYSTORE_PASSWORD";
+    /// How long to wait for the account snapshot published on subscribing.
+    const ACCOUNT_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    #[derive(Parser)]
+    #[command(
+        name = "handle-synths",
+        about = "handle-synths account and trading operations"
+    )]
+    pub struct Cli {
+        #[arg(
+            long,
+            env = "HANDLE_SYNTHS_PRIVATE_KEY",
+            hide_env_values = true,
+            required_unless_present = "keystore",
+            conflicts_with = "keystore"
+        )]
+        private_key: Option<String>,
+        /// An encrypted JSON keystore to sign with instead of a private key.
+        #[arg(long, env = "HANDLE_SYNTHS_KEYSTORE")]
+        keystore: Option<PathBuf>,
+        /// A file containing the keystore password.
+        /// If not set, the password is read from `HANDLE_SYNTHS_KEYSTORE_PASSWORD`
+        /// or prompted for.
+        #[arg(long, requires = "keystore")]
+        keystore_password_file: Option<PathBuf>,
+        #[arg(long, env = "HANDLE_SYNTHS_RPC_URL")]
+        rpc_url: String,
+        /// The trade server URL.
+        /// Defaults to the server for the RPC's network.
+        #[arg(long, env = "HANDLE_SYNTHS_WS_URL")]
+        ws_url: Option<String>,
+        /// A JSON file of additional network configs.
+        #[arg(long, env = "HANDLE_SYNTHS_CONFIG_PATH")]
+        config: Option<PathBuf>,
+        #[command(subcommand)]
+        command: Command,
+    }
+
+    #[derive(Subcommand)]
+    enum Command {
+        /// Opens a new trade account with an initial deposit.
+        OpenAccount {
+            #[arg(long)]
+            amount: BigDecimal,
+            #[arg(long)]
+            token: Address,
+            #[arg(long)]
+            gasless: bool,
+            #[arg(long)]
+            referral_code: Option<String>,
+        },
+        Deposit {
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            amount: BigDecimal,
+            #[arg(long)]
+            token: Address,
+            #[arg(long)]
+            gasless: bool,
+        },
+        Withdraw {
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            amount: BigDecimal,
+            #[arg(long)]
+            token: Address,
+            #[arg(long)]
+            recipient: Address,
+            /// Swaps the withdrawn token to this token via the hPSM.
+            #[arg(long)]
+            psm_token: Option<Address>,
+        },
+        GrantRole {
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            user: Address,
+            #[arg(long)]
+            role: RoleArg,
+        },
+        RevokeRole {
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            user: Address,
+            #[arg(long)]
+            role: RoleArg,
+        },
+        PlaceOrder {
+            #[arg(long)]
+            account_id: AccountId,
+            #[command(flatten)]
+            order: OrderArgs,
+        },
+        CancelOrder {
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            order_id: Uuid,
+        },
+        ReplaceOrder {
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            order_id: Uuid,
+            #[command(flatten)]
+            order: OrderArgs,
+        },
+        /// Lists the account's open positions.
+        /// Waits for the account snapshot that the trade server publishes on subscribing.
+        Positions {
+            #[arg(long)]
+            account_id: AccountId,
+        },
+        /// Lists the account's open orders.
+        /// Waits for the account snapshot that the trade server publishes on subscribing.
+        OpenOrders {
+            #[arg(long)]
+            account_id: AccountId,
+        },
+        /// Sets a registered system param as a protocol admin.
+        SetSystemParam {
+            /// The admin's trade account.
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            param_id: String,
+            /// The human-readable value, e.g. `0.001`, `true` or an address,
+            /// which is parsed as the param's registered kind.
+            #[arg(long)]
+            value: String,
+            #[command(flatten)]
+            registry: RegistryArgs,
+        },
+        /// Sets a system param from its raw value as a protocol admin,
+        /// e.g. for params that are not registered.
+        SetSystemParamRaw {
+            /// The admin's trade account.
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            param_id: String,
+            /// The 32 hex encoded bytes of the value.
+            #[arg(long)]
+            value: String,
+        },
+        /// Lists the system params set on chain that do not match the registry.
+        CheckSystemParams {
+            /// The block to read params set since.
+            #[arg(long, default_value_t = 0)]
+            from_block: u64,
+            #[command(flatten)]
+            registry: RegistryArgs,
+        },
+        /// Clears a system param as a protocol admin.
+        ClearSystemParam {
+            /// The admin's trade account.
+            #[arg(long)]
+            account_id: AccountId,
+            #[arg(long)]
+            param_id: String,
+        },
+        /// Subscribes to a topic and prints its publications as JSON lines.
+        Subscribe {
+            #[command(subcommand)]
+            topic: TopicArg,
+        },
+    }
+
+    #[derive(Subcommand)]
+    enum TopicArg {
+        TradeAccount {
+            account_id: AccountId,
+        },
+        /// LP pair state and tradeability.
+        LiquidityPool {
+            #[arg(value_parser = parse_lp_id)]
+            lp_id: LiquidityPoolId,
+        },
+        /// All trades in the LP.
+        LiquidityPoolTrade {
+            #[arg(value_parser = parse_lp_id)]
+            lp_id: LiquidityPoolId,
+        },
+    }
+
+    #[derive(Args)]
+    struct OrderArgs {
+        #[arg(long, value_parser = parse_lp_id)]
+        lp_id: LiquidityPoolId,
+        /// The pair, e.g. ETH/USD.
+        #[arg(long)]
+        pair: Pair,
+        /// The signed order size; negative for selling.
+        #[arg(long, allow_hyphen_values = true)]
+        size: BigDecimal,
+        #[arg(long, value_enum, default_value_t = SizeUnitArg::Lot)]
+        unit: SizeUnitArg,
+        #[arg(long, value_enum, default_value_t = OrderKindArg::Market)]
+        kind: OrderKindArg,
+        #[arg(long)]
+        limit_price: Option<BigDecimal>,
+        #[arg(long)]
+        trigger_price: Option<BigDecimal>,
+        #[arg(long)]
+        expiry_timestamp_unix_millis: Option<i64>,
+    }
+
+    #[derive(Copy, Clone, ValueEnum)]
+    enum SizeUnitArg {
+        Lot,
+        Lpc,
+    }
+
+    #[derive(Copy, Clone, ValueEnum)]
+    enum OrderKindArg {
+        Market,
+        Limit,
+        StopMarket,
+        StopLimit,
+        LimitTrigger,
+    }
+
+    #[derive(Args)]
+    struct RegistryArgs {
+        /// A JSON file of the known system params and their kinds.
+        #[arg(long = "system-params", env = "HANDLE_SYNTHS_SYSTEM_PARAMS_PATH")]
+        path: PathBuf,
+    }
+
+    #[derive(Copy, Clone, ValueEnum)]
+    enum RoleArg {
+        Owner,
+        Trader,
+        Withdraw,
+        Deposit,
+    }
+
+    pub async fn run(cli: Cli) -> eyre::Result<()> {
+        // The config file is read from HANDLE_SYNTHS_CONFIG_PATH by clap,
+        // so only the network variables are read from the environment here.
+        let mut config = Config::builder().with_defaults()?;
+        if let Some(path) = cli.config {
+            config = config.with_file(path)?;
+        }
+        set_config(config.with_env_network()?.build());
+        let wallet = match (cli.private_key, cli.keystore) {
+            (Some(private_key), _) => private_key.parse::<LocalWallet>()?,
+            (None, Some(keystore)) => {
+                let password = match cli.keystore_password_file {
+                    Some(path) => KeystorePassword::File(path),
+                    None if env::var(KEYSTORE_PASSWORD_ENV).is_ok() => {
+                        KeystorePassword::Env(KEYSTORE_PASSWORD_ENV.to_owned())
+                    }
+                    None => KeystorePassword::Prompt,
+                };
+                decrypt_keystore(keystore, &password)?
+            }
+            (None, None) => return Err(eyre!("a private key or keystore is required")),
+        };
+        let user = User::connect(wallet, &cli.rpc_url).await?;
+        let ws_url = match cli.ws_url {
+            Some(ws_url) => ws_url,
+            None => get_ws_url(&user).await?,
+        };
+        let connection = ClientConnection::connect(&ws_url).await?;
+        let account = |account_id: AccountId| {
+            TradeAccountClient::from_existing(account_id, user.clone(), connection.clone())
+        };
+        match cli.command {
+            Command::OpenAccount {
+                amount,
+                token,
+                gasless,
+                referral_code,
+            } => {
+                let account = TradeAccountClient::open(
+                    amount,
+                    token,
+                    gasless,
+                    referral_code,
+                    user.clone(),
+                    connection.clone(),
+                )
+                .await?;
+                print_json(&serde_json::json!({ "accountId": account.id }))
+            }
+            Command::Deposit {
+                account_id,
+                amount,
+                token,
+                gasless,
+            } => print_json(&account(account_id).deposit(amount, token, gasless).await?),
+            Command::Withdraw {
+                account_id,
+                amount,
+                token,
+                recipient,
+                psm_token,
+            } => print_json(
+                &account(account_id)
+                    .withdraw(amount, token, recipient, psm_token)
+                    .await?,
+            ),
+            Command::GrantRole {
+                account_id,
+                user,
+                role,
+            } => print_json(
+                &account(account_id)
+                    .grant_account_user_role(user, role.into())
+                    .await?,
+            ),
+            Command::RevokeRole {
+                account_id,
+                user,
+                role,
+            } => print_json(
+                &account(account_id)
+                    .revoke_account_user_role(user, role.into())
+                    .await?,
+            ),
+            Command::PlaceOrder { account_id, order } => {
+                let (size, kind) = order.get_size_and_kind()?;
+                let order = account(account_id)
+                    .place_order(
+                        order.lp_id,
+                        order.pair,
+                        size,
+                        kind,
+                        order.expiry_timestamp_unix_millis,
+                    )
+                    .await?;
+                print_json(&order)
+            }
+            Command::CancelOrder {
+                account_id,
+                order_id,
+            } => print_json(&account(account_id).cancel_order(order_id).await?),
+            Command::ReplaceOrder {
+                account_id,
+                order_id,
+                order,
+            } => {
+                let (size, kind) = order.get_size_and_kind()?;
+                let event = account(account_id)
+                    .replace_order(
+                        order_id,
+                        order.lp_id,
+                        order.pair,
+                        size,
+                        kind,
+                        order.expiry_timestamp_unix_millis,
+                    )
+                    .await?;
+                print_json(&event)
+            }
+            Command::Positions { account_id } => {
+                let snapshot = get_account_snapshot(&account(account_id)).await?;
+                print_json(&snapshot.positions)
+            }
+            Command::OpenOrders { account_id } => {
+                let snapshot = get_account_snapshot(&account(account_id)).await?;
+                print_json(&snapshot.open_orders)
+            }
+            Command::SetSystemParam {
+                account_id,
+                param_id,
+                value,
+                registry,
+            } => {
+                let param =
+                    SystemParamRegistry::from_file(registry.path)?.parse(&param_id, &value)?;
+                let admin = ProtocolAdminClient::new(account_id, user, connection);
+                print_json(&admin.set_system_param(&param).await?)
+            }
+            Command::SetSystemParamRaw {
+                account_id,
+                param_id,
+                value,
+            } => {
+                let value: Bytes = value.parse()?;
+                let admin = ProtocolAdminClient::new(account_id, user, connection);
+                print_json(&admin.set_system_param_raw(&param_id, value).await?)
+            }
+            Command::CheckSystemParams {
+                from_block,
+                registry,
+            } => {
+                let registry = SystemParamRegistry::from_file(registry.path)?;
+                let set_params = user.get_set_system_params(from_block).await?;
+                print_json(&registry.check(set_params))
+            }
+            Command::ClearSystemParam {
+                account_id,
+                param_id,
+            } => {
+                let admin = ProtocolAdminClient::new(account_id, user, connection);
+                print_json(&admin.clear_system_param(&param_id).await?)
+            }
+            Command::Subscribe { topic } => {
+                let mut subscription = connection.subscribe(topic.into()).await?;
+                while let Some(publication) = subscription.next().await {
+                    print_json(&publication)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    impl OrderArgs {
+        fn get_size_and_kind(&self) -> eyre::Result<(TradeSize, OrderKind)> {
+            let size = match self.unit {
+                SizeUnitArg::Lot => TradeSize::Lot(self.size.clone()),
+                SizeUnitArg::Lpc => TradeSize::Lpc(self.size.clone()),
+            };
+            let limit_price = || {
+                self.limit_price
+                    .clone()
+                    .ok_or_else(|| eyre!("--limit-price is required"))
+            };
+            let trigger_price = || {
+                self.trigger_price
+                    .clone()
+                    .ok_or_else(|| eyre!("--trigger-price is required"))
+            };
+            let kind = match self.kind {
+                OrderKindArg::Market => OrderKind::Market,
+                OrderKindArg::Limit => OrderKind::Limit(LimitOrderArgs {
+                    limit_price: limit_price()?,
+                }),
+                OrderKindArg::StopMarket => OrderKind::StopMarket(StopMarketOrderArgs {
+                    trigger_price: trigger_price()?,
+                    stop_loss: None,
+                }),
+                OrderKindArg::StopLimit => OrderKind::StopLimit(StopLimitOrderArgs {
+                    limit_price: limit_price()?,
+                    trigger_price: trigger_price()?,
+                }),
+                OrderKindArg::LimitTrigger => OrderKind::LimitTrigger(LimitTriggerOrderArgs {
+                    trigger_price: trigger_price()?,
+                    take_profit: None,
+                }),
+            };
+            Ok((size, kind))
+        }
+    }
+
+    impl From<RoleArg> for AccountRole {
+        fn from(value: RoleArg) -> Self {
+            match value {
+                RoleArg::Owner => AccountRole::Owner,
+                RoleArg::Trader => AccountRole::Trader,
+                RoleArg::Withdraw => AccountRole::Withdraw,
+                RoleArg::Deposit => AccountRole::Deposit,
+            }
+        }
+    }
+
+    impl From<TopicArg> for SubscriptionTopic {
+        fn from(value: TopicArg) -> Self {
+            match value {
+                TopicArg::TradeAccount { account_id } => {
+                    SubscriptionTopic::TradeAccount(account_id)
+                }
+                TopicArg::LiquidityPool { lp_id } => SubscriptionTopic::LiquidityPool(lp_id),
+                TopicArg::LiquidityPoolTrade { lp_id } => {
+                    SubscriptionTopic::LiquidityPoolTrade(lp_id)
+                }
+            }
+        }
+    }
+
+    async fn get_ws_url(user: &User) -> eyre::Result<String> {
+        let chain_id = user
+            .contracts
+            .account
+            .client()
+            .get_chainid()
+            .await?
+            .as_u64();
+        let config = get_network_config_by_chain_id(chain_id)
+            .ok_or_else(|| eyre!("no trade server for chain ID {chain_id}"))?;
+        Ok(config.ws)
+    }
+
+    /// Waits for the first account snapshot published after subscribing.
+    /// There is no request for a snapshot, so this relies on the trade server
+    /// publishing one on subscribing, and fails after [ACCOUNT_SNAPSHOT_TIMEOUT]
+    /// otherwise.
+    async fn get_account_snapshot(
+        account: &TradeAccountClient,
+    ) -> eyre::Result<handle_synths_client::interface::AccountSnapshot> {
+        let mut mirror = account.mirror_state().await?;
+        let wait_for_snapshot = async {
+            loop {
+                let state = mirror.changed().await?;
+                if let Some(TradeAccountStateChange::Snapshot) = state.last_change() {
+                    return Ok::<_, eyre::Report>(state.snapshot().clone());
+                }
+            }
+        };
+        tokio::time::timeout(ACCOUNT_SNAPSHOT_TIMEOUT, wait_for_snapshot)
+            .await
+            .map_err(|_| eyre!("timed out waiting for account snapshot"))?
+    }
+
+    fn parse_lp_id(value: &str) -> Result<LiquidityPoolId, String> {
+        LiquidityPoolId::from_hex_str(value.trim_start_matches("0x")).map_err(|e| e.to_string())
+    }
+
+    fn print_json<T: Serialize>(value: &T) -> eyre::Result<()> {
+        println!("{}", serde_json::to_string(value)?);
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use clap::CommandFactory;
+
+        #[test]
+        fn cli() {
+            Cli::command().debug_assert();
+        }
+    }
+}
@@ -1,92 +1,675 @@
-use crate::interface::{MessageId, Request, RequestContent, Response};
+use crate::error::ClientError;
+use crate::interface::{
+    MessageId, Publication, Request, RequestContent, Response, ResponseContent, SubscriptionTopic,
+};
 use ethers::prelude::StreamExt;
 use futures::stream::{SplitSink, SplitStream};
-use futures::SinkExt;
+use futures::{SinkExt, Stream};
 use rand::random;
+use std::collections::HashMap;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::sync::oneshot::{Receiver, Sender};
-use tokio::sync::{oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio_tungstenite::tungstenite::{Error, Message};
 use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(250);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
 pub type SocketWrite = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
 type SocketRead = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
+type ResponseListeners = Arc<std::sync::Mutex<Vec<ResponseListener>>>;
+type PublicationListeners = Vec<(u64, mpsc::UnboundedSender<Publication>)>;
 
+/// A connection to the trade server.
+/// If the socket closes, the connection is re-established with exponential
+/// backoff, in-flight requests fail with [ClientError::ConnectionLost]
+/// and active subscriptions are replayed on the new socket.
+/// The streams of subscriptions that fail to be replayed end.
 #[derive(Debug, Clone)]
 pub struct ClientConnection {
-    tx: Arc<Mutex<SocketWrite>>,
-    response_listeners: Arc<Mutex<Vec<(MessageId, Sender<Response>)>>>,
+    inner: Arc<ConnectionInner>,
+    request_timeout: Duration,
+    /// Stops the connection task once all clones of the connection are dropped.
+    _shutdown: Arc<Sender<()>>,
+}
+
+#[derive(Debug)]
+struct ConnectionInner {
+    /// The socket writer, which is `None` while reconnecting.
+    tx: Mutex<Option<SocketWrite>>,
+    response_listeners: ResponseListeners,
+    subscriptions: std::sync::Mutex<Subscriptions>,
+}
+
+#[derive(Debug, Default)]
+struct Subscriptions {
+    /// Active subscriptions and their server-assigned subscription IDs.
+    ids: HashMap<SubscriptionTopic, MessageId>,
+    /// Senders for the [Subscription] handles of each topic.
+    publication_listeners: HashMap<SubscriptionTopic, PublicationListeners>,
+    /// Topics with a subscribe request in flight, and the calls waiting
+    /// for it to finish, which are notified by dropping their senders.
+    pending: HashMap<SubscriptionTopic, Vec<Sender<()>>>,
+}
+
+#[derive(Debug)]
+struct ResponseListener {
+    message_id: MessageId,
+    /// The topic, if the request is a subscription.
+    /// This is used to record the subscription ID as soon as it is received,
+    /// so that no publications are missed.
+    topic: Option<SubscriptionTopic>,
+    sender: Sender<Response>,
+}
+
+/// Removes a response listener when dropped, so that listeners do not leak
+/// if the request times out or the caller stops awaiting the response.
+struct ResponseListenerGuard {
+    message_id: MessageId,
+    response_listeners: ResponseListeners,
+}
+
+/// Removes a pending subscription when dropped, waking the calls waiting
+/// for it whether the subscribe request succeeded, failed or was cancelled.
+struct PendingSubscriptionGuard<'a> {
+    topic: &'a SubscriptionTopic,
+    subscriptions: &'a std::sync::Mutex<Subscriptions>,
+}
+
+/// A stream of publications for a subscription topic.
+/// Subscriptions to the same topic share a single server subscription,
+/// which is unsubscribed from once all of its handles are dropped.
+#[derive(Debug)]
+pub struct Subscription {
+    topic: SubscriptionTopic,
+    listener_id: u64,
+    rx: mpsc::UnboundedReceiver<Publication>,
+    connection: ClientConnection,
 }
 
 impl ClientConnection {
-    pub async fn connect(ws_url: &str) -> eyre::Result<Self> {
+    pub async fn connect(ws_url: &str) -> Result<Self, ClientError> {
         let (ws_stream, _) = connect_async(ws_url).await?;
         let (tx, rx) = ws_stream.split();
-        let response_listeners = Arc::new(Mutex::new(Vec::new()));
-        tokio::spawn(listen_for_messages(rx, response_listeners.clone()));
+        let inner = Arc::new(ConnectionInner {
+            tx: Mutex::new(Some(tx)),
+            response_listeners: Arc::new(std::sync::Mutex::new(Vec::new())),
+            subscriptions: std::sync::Mutex::new(Subscriptions::default()),
+        });
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        tokio::spawn(run_connection(
+            ws_url.to_owned(),
+            rx,
+            inner.clone(),
+            shutdown_rx,
+        ));
         Ok(Self {
-            tx: Arc::new(Mutex::new(tx)),
-            response_listeners,
+            inner,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            _shutdown: Arc::new(shutdown_tx),
         })
     }
 
-    pub async fn send_raw_message<S>(&self, message: S) -> Result<(), Error>
+    /// Sets the timeout used by [ClientConnection::send_request].
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        self.request_timeout
+    }
+
+    /// Returns whether the socket is currently connected.
+    pub async fn is_connected(&self) -> bool {
+        self.inner.tx.lock().await.is_some()
+    }
+
+    /// Returns the active subscription topics.
+    pub fn subscriptions(&self) -> Vec<SubscriptionTopic> {
+        self.inner
+            .subscriptions
+            .lock()
+            .unwrap()
+            .ids
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Subscribes to the topic, returning a stream of its publications.
+    /// Dropping the returned handle unsubscribes from the topic.
+    pub async fn subscribe(&self, topic: SubscriptionTopic) -> Result<Subscription, ClientError> {
+        let listener_id = random::<u64>();
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inner
+            .subscriptions
+            .lock()
+            .unwrap()
+            .publication_listeners
+            .entry(topic.clone())
+            .or_default()
+            .push((listener_id, tx));
+        // The handle unsubscribes on drop, including if subscribing fails below.
+        let subscription = Subscription {
+            topic: topic.clone(),
+            listener_id,
+            rx,
+            connection: self.clone(),
+        };
+        loop {
+            let pending_rx = {
+                let mut subscriptions = self.inner.subscriptions.lock().unwrap();
+                if subscriptions.ids.contains_key(&topic) {
+                    return Ok(subscription);
+                }
+                match subscriptions.pending.get_mut(&topic) {
+                    Some(waiters) => {
+                        let (waiter_tx, waiter_rx) = oneshot::channel();
+                        waiters.push(waiter_tx);
+                        waiter_rx
+                    }
+                    None => {
+                        subscriptions.pending.insert(topic.clone(), Vec::new());
+                        break;
+                    }
+                }
+            };
+            // Another call is subscribing to the topic, so check again once
+            // it has finished, as it may have failed.
+            _ = pending_rx.await;
+        }
+        let _pending = PendingSubscriptionGuard {
+            topic: &topic,
+            subscriptions: &self.inner.subscriptions,
+        };
+        let response = self
+            .send_request(RequestContent::Subscribe(topic.clone()))
+            .await?;
+        match response.content()? {
+            ResponseContent::Subscription(_) => Ok(subscription),
+            content => Err(ClientError::unexpected_response("subscription", content)),
+        }
+    }
+
+    pub async fn send_raw_message<S>(&self, message: S) -> Result<(), ClientError>
+    where
+        S: Into<String>,
+    {
+        self.inner.send_raw_message(message).await
+    }
+
+    /// Sends a request and waits for its response, using the connection's
+    /// default request timeout.
+    pub async fn send_request(&self, content: RequestContent) -> Result<Response, ClientError> {
+        self.send_request_with_timeout(content, self.request_timeout)
+            .await
+    }
+
+    pub async fn send_request_with_timeout(
+        &self,
+        content: RequestContent,
+        timeout: Duration,
+    ) -> Result<Response, ClientError> {
+        self.inner.send_request(content, timeout).await
+    }
+}
+
+impl ConnectionInner {
+    async fn send_raw_message<S>(&self, message: S) -> Result<(), ClientError>
     where
         S: Into<String>,
     {
         let mut tx = self.tx.lock().await;
-        tx.send(Message::text(message)).await
+        let Some(tx) = tx.as_mut() else {
+            return Err(Error::AlreadyClosed.into());
+        };
+        Ok(tx.send(Message::text(message)).await?)
     }
 
-    pub async fn send_request(&self, content: RequestContent) -> eyre::Result<Response> {
+    async fn send_request(
+        &self,
+        content: RequestContent,
+        timeout: Duration,
+    ) -> Result<Response, ClientError> {
         let id = random::<u64>().to_string();
         let request = Request {
             id: Some(id.clone()),
             content,
         };
         let serialized = serde_json::to_string(&request)?;
-        let rx = self.add_response_listener(id).await;
+        let topic = match &request.content {
+            RequestContent::Subscribe(topic) => Some(topic.clone()),
+            _ => None,
+        };
+        // The guard removes the listener once this future resolves or is dropped.
+        let (rx, _guard) = self.add_response_listener(id.clone(), topic);
         self.send_raw_message(&serialized).await?;
-        // TODO: add timeout error handling.
-        let response = rx.await?;
+        let response = match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => return Err(ClientError::ConnectionLost),
+            Err(_) => return Err(ClientError::Timeout { id, timeout }),
+        };
+        if let RequestContent::Unsubscribe(subscription_id) = &request.content {
+            self.remove_subscription_id(subscription_id);
+        }
         Ok(response)
     }
 
-    async fn add_response_listener(&self, message_id: MessageId) -> Receiver<Response> {
-        let mut listeners = self.response_listeners.lock().await;
-        let (tx, rx) = oneshot::channel();
-        listeners.push((message_id, tx));
-        rx
+    fn add_response_listener(
+        &self,
+        message_id: MessageId,
+        topic: Option<SubscriptionTopic>,
+    ) -> (Receiver<Response>, ResponseListenerGuard) {
+        let (sender, rx) = oneshot::channel();
+        let mut listeners = self.response_listeners.lock().unwrap();
+        listeners.push(ResponseListener {
+            message_id: message_id.clone(),
+            topic,
+            sender,
+        });
+        let guard = ResponseListenerGuard {
+            message_id,
+            response_listeners: self.response_listeners.clone(),
+        };
+        (rx, guard)
+    }
+
+    fn remove_subscription_id(&self, subscription_id: &MessageId) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .ids
+            .retain(|_, id| id != subscription_id);
     }
-}
 
-async fn listen_for_messages(
-    rx: SocketRead,
-    response_listeners: Arc<Mutex<Vec<(MessageId, Sender<Response>)>>>,
-) {
-    rx.for_each(|msg| async {
-        let Ok(msg) = msg else {
+    /// Marks the socket as disconnected and fails all in-flight requests.
+    async fn disconnect(&self) {
+        *self.tx.lock().await = None;
+        // Dropping the senders resolves the pending requests with an error.
+        self.response_listeners.lock().unwrap().clear();
+    }
+
+    async fn resubscribe(&self) {
+        let topics: Vec<SubscriptionTopic> = self
+            .subscriptions
+            .lock()
+            .unwrap()
+            .ids
+            .keys()
+            .cloned()
+            .collect();
+        for topic in topics {
+            let result = self
+                .send_request(
+                    RequestContent::Subscribe(topic.clone()),
+                    DEFAULT_REQUEST_TIMEOUT,
+                )
+                .await;
+            let error = match result.map(Response::content) {
+                Ok(Ok(_)) => continue,
+                Ok(Err(error)) => ClientError::from(error),
+                Err(error) => error,
+            };
+            log::warn!("failed to resubscribe to {topic:?}: {error}");
+            self.remove_subscription(&topic);
+        }
+    }
+
+    /// Removes the subscription and its listeners, ending the streams of
+    /// its [Subscription] handles.
+    fn remove_subscription(&self, topic: &SubscriptionTopic) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.ids.remove(topic);
+        subscriptions.publication_listeners.remove(topic);
+    }
+
+    fn handle_message(&self, text: &str) {
+        let Ok(response) = serde_json::from_str::<Response>(text) else {
             return;
         };
-        let Ok(text) = msg.to_text() else {
+        let Some(response_id) = &response.id else {
             return;
         };
-        let Ok(response) = serde_json::from_str::<Response>(text) else {
+        if let Some(ResponseContent::Publication(publication)) = &response.content.result {
+            self.publish(response_id, publication);
             return;
-        };
-        let Some(response_id) = &response.id else {
+        }
+        let Ok(mut response_listeners) = self.response_listeners.lock() else {
             return;
         };
-        let mut response_listeners = response_listeners.lock().await;
         let listener_index_opt = response_listeners
-            .iter_mut()
-            .position(|(id, _)| id == response_id);
+            .iter()
+            .position(|listener| listener.message_id == *response_id);
         let Some(listener_index) = listener_index_opt else {
             return;
         };
-        let (_, sender) = response_listeners.swap_remove(listener_index);
-        _ = sender.send(response);
-    })
-    .await;
+        let listener = response_listeners.swap_remove(listener_index);
+        if let (Some(topic), Some(ResponseContent::Subscription(subscription_id))) =
+            (listener.topic, &response.content.result)
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            subscriptions.ids.insert(topic, subscription_id.clone());
+        }
+        _ = listener.sender.send(response);
+    }
+
+    /// Forwards the publication to the handles subscribed to its topic.
+    fn publish(&self, subscription_id: &MessageId, publication: &Publication) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let topic_opt = subscriptions
+            .ids
+            .iter()
+            .find(|(_, id)| *id == subscription_id)
+            .map(|(topic, _)| topic.clone());
+        let Some(topic) = topic_opt else {
+            return;
+        };
+        let Some(listeners) = subscriptions.publication_listeners.get_mut(&topic) else {
+            return;
+        };
+        listeners.retain(|(_, tx)| tx.send(publication.clone()).is_ok());
+    }
+}
+
+impl Drop for ResponseListenerGuard {
+    fn drop(&mut self) {
+        let Ok(mut listeners) = self.response_listeners.lock() else {
+            return;
+        };
+        listeners.retain(|listener| listener.message_id != self.message_id);
+    }
+}
+
+impl Drop for PendingSubscriptionGuard<'_> {
+    fn drop(&mut self) {
+        let Ok(mut subscriptions) = self.subscriptions.lock() else {
+            return;
+        };
+        subscriptions.pending.remove(self.topic);
+    }
+}
+
+impl Subscription {
+    pub fn topic(&self) -> &SubscriptionTopic {
+        &self.topic
+    }
+
+    /// Returns the server-assigned subscription ID.
+    /// This changes whenever the subscription is replayed after a reconnection.
+    pub fn id(&self) -> Option<MessageId> {
+        let subscriptions = self.connection.inner.subscriptions.lock().unwrap();
+        subscriptions.ids.get(&self.topic).cloned()
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Publication;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let subscription_id_opt = {
+            let Ok(mut subscriptions) = self.connection.inner.subscriptions.lock() else {
+                return;
+            };
+            let Some(listeners) = subscriptions.publication_listeners.get_mut(&self.topic) else {
+                return;
+            };
+            listeners.retain(|(id, _)| *id != self.listener_id);
+            if !listeners.is_empty() {
+                return;
+            }
+            subscriptions.publication_listeners.remove(&self.topic);
+            subscriptions.ids.remove(&self.topic)
+        };
+        let Some(subscription_id) = subscription_id_opt else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let connection = self.connection.clone();
+        runtime.spawn(async move {
+            let request = RequestContent::Unsubscribe(subscription_id);
+            if let Err(error) = connection.send_request(request).await {
+                log::warn!("failed to unsubscribe: {error}");
+            }
+        });
+    }
+}
+
+/// Listens for messages and reconnects whenever the socket closes,
+/// until the connection is shut down.
+async fn run_connection(
+    ws_url: String,
+    mut rx: SocketRead,
+    inner: Arc<ConnectionInner>,
+    mut shutdown: Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            _ = listen_for_messages(&mut rx, &inner) => {}
+            _ = &mut shutdown => return,
+        }
+        log::warn!("connection to {ws_url} lost, reconnecting");
+        inner.disconnect().await;
+        let mut delay = RECONNECT_INITIAL_DELAY;
+        let ws_stream = loop {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = &mut shutdown => return,
+            }
+            match connect_async(&ws_url).await {
+                Ok((ws_stream, _)) => break ws_stream,
+                Err(error) => {
+                    log::warn!("failed to reconnect to {ws_url}: {error}");
+                    delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                }
+            }
+        };
+        let (tx, new_rx) = ws_stream.split();
+        rx = new_rx;
+        *inner.tx.lock().await = Some(tx);
+        log::info!("reconnected to {ws_url}");
+        let inner = inner.clone();
+        tokio::spawn(async move { inner.resubscribe().await });
+    }
+}
+
+/// Handles incoming messages until the socket closes.
+async fn listen_for_messages(rx: &mut SocketRead, inner: &ConnectionInner) {
+    while let Some(msg) = rx.next().await {
+        let Ok(msg) = msg else {
+            return;
+        };
+        let Ok(text) = msg.to_text() else {
+            continue;
+        };
+        inner.handle_message(text);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::AccountSnapshot;
+    use crate::mock_server::MockServer;
+
+    #[tokio::test]
+    async fn subscription() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let topic = SubscriptionTopic::TradeAccount(1);
+        // Given two handles subscribed to the same topic;
+        let mut subscription = connection.subscribe(topic.clone()).await.unwrap();
+        let second_subscription = connection.subscribe(topic.clone()).await.unwrap();
+        // Then only one server subscription should have been made;
+        assert_eq!(server.subscriptions(), vec![topic.clone()]);
+        // When the server publishes to the topic;
+        let snapshot = AccountSnapshot {
+            id: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            server.publish(&topic, Publication::TradeAccount(snapshot)),
+            1
+        );
+        // Then the publication should be received;
+        let publication = subscription.next().await.unwrap();
+        assert!(matches!(publication, Publication::TradeAccount(s) if s.id == 1));
+        // When all handles are dropped;
+        drop(subscription);
+        drop(second_subscription);
+        // Then the topic should be unsubscribed from;
+        let unsubscribe = server
+            .wait_for_request(
+                |request| matches!(request, RequestContent::Unsubscribe(_)),
+                Duration::from_secs(5),
+            )
+            .await;
+        assert!(unsubscribe.is_some());
+    }
+
+    #[tokio::test]
+    async fn concurrent_subscriptions() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let topic = SubscriptionTopic::TradeAccount(1);
+        // When the same topic is subscribed to concurrently;
+        let (first, second) = tokio::join!(
+            connection.subscribe(topic.clone()),
+            connection.subscribe(topic.clone())
+        );
+        let _subscriptions = (first.unwrap(), second.unwrap());
+        // Then only one subscribe request should have been sent.
+        let subscribe_count = server
+            .requests()
+            .iter()
+            .filter(|request| matches!(request, RequestContent::Subscribe(_)))
+            .count();
+        assert_eq!(subscribe_count, 1);
+        assert_eq!(connection.subscriptions(), vec![topic]);
+    }
+
+    #[tokio::test]
+    async fn request_timeout() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url())
+            .await
+            .unwrap()
+            .with_request_timeout(Duration::from_millis(100));
+        server.ignore_next_request();
+        let result = connection.send_request(RequestContent::GetLpConfig).await;
+        assert!(matches!(result, Err(ClientError::Timeout { .. })));
+    }
+
+    #[tokio::test]
+    async fn cancelled_request() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        server.ignore_next_request();
+        // Given a request that is waiting for its response;
+        let mut request = Box::pin(connection.send_request(RequestContent::GetLpConfig));
+        let result = tokio::time::timeout(Duration::from_millis(100), &mut request).await;
+        assert!(result.is_err());
+        let listeners = || connection.inner.response_listeners.lock().unwrap().len();
+        assert_eq!(listeners(), 1);
+        // When the caller stops awaiting it;
+        drop(request);
+        // Then its response listener should be removed.
+        assert_eq!(listeners(), 0);
+    }
+
+    /// Waits until the server has received the number of subscribe requests.
+    async fn wait_for_subscribe_requests(server: &MockServer, count: usize) {
+        let get_count = || {
+            server
+                .requests()
+                .iter()
+                .filter(|request| matches!(request, RequestContent::Subscribe(_)))
+                .count()
+        };
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while get_count() < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconnection() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let topic = SubscriptionTopic::TradeAccount(1);
+        let mut subscription = connection.subscribe(topic.clone()).await.unwrap();
+        let subscription_id = subscription.id().unwrap();
+        // Given a request that is waiting for its response;
+        server.ignore_next_request();
+        let request = tokio::spawn({
+            let connection = connection.clone();
+            async move { connection.send_request(RequestContent::GetLpConfig).await }
+        });
+        server
+            .wait_for_request(
+                |request| matches!(request, RequestContent::GetLpConfig),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        // When the connection is lost;
+        server.disconnect_clients();
+        // Then the request should fail;
+        let result = request.await.unwrap();
+        assert!(matches!(result, Err(ClientError::ConnectionLost)));
+        // and the topic should be resubscribed to once reconnected;
+        wait_for_subscribe_requests(&server, 2).await;
+        assert_eq!(server.subscriptions(), vec![topic.clone()]);
+        // and publications should resume on the same handle.
+        let snapshot = AccountSnapshot {
+            id: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            server.publish(&topic, Publication::TradeAccount(snapshot)),
+            1
+        );
+        let publication = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(publication, Publication::TradeAccount(s) if s.id == 1));
+        assert_ne!(subscription.id().unwrap(), subscription_id);
+        assert!(connection.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn failed_resubscription() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let topic = SubscriptionTopic::TradeAccount(1);
+        let mut subscription = connection.subscribe(topic.clone()).await.unwrap();
+        // Given the server rejects subscriptions;
+        server.reject_subscriptions(true);
+        // When the connection is lost and the topic cannot be resubscribed to;
+        server.disconnect_clients();
+        // Then the subscription stream should end.
+        let publication = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+            .await
+            .unwrap();
+        assert!(publication.is_none());
+        assert!(connection.subscriptions().is_empty());
+    }
 }
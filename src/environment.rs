@@ -1,36 +1,82 @@
-use crate::interface::contract_types::{Account, LiquidityPool, Treasury};
+pub use crate::interface::signing::ACCOUNT_MESSAGE_SCOPE;
+
+use crate::error::ClientError;
+use crate::interface::contract_types::{Account, Beacon, LiquidityPool, Treasury};
 use ethers::addressbook::Address;
 use ethers::contract::Lazy;
-use ethers::middleware::signer::SignerMiddlewareError;
 use ethers::middleware::SignerMiddleware;
-use ethers::prelude::{Http, LocalWallet, Middleware, Provider, ProviderExt};
-use ethers::utils::keccak256;
-use eyre::eyre;
+use ethers::prelude::{Http, LocalWallet, Middleware, Provider, ProviderExt, Signer};
 use pws::{connect_persistent_websocket_async, WsMessageReceiver, WsMessageSender};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
+use thiserror::Error;
 use url::Url;
 
 pub const DEPOSIT_TOKEN_DECIMALS: u8 = 18;
 
-pub static CONFIG: Lazy<Config> = Lazy::new(|| Config::from_build_json().unwrap());
+/// The environment variable for the path of a JSON config file.
+pub const CONFIG_PATH_ENV: &str = "HANDLE_SYNTHS_CONFIG_PATH";
+/// The prefix of the environment variables for a custom network.
+pub const NETWORK_ENV_PREFIX: &str = "HANDLE_SYNTHS_NETWORK";
+
+/// The built-in networks.
+#[deprecated(note = "use `get_config`, which includes custom networks")]
+pub static CONFIG: Lazy<DeployedNetworks> = Lazy::new(|| {
+    let config = get_build_config();
+    let network = |name: &str| {
+        config
+            .network(name)
+            .cloned()
+            .unwrap_or_else(|| panic!("missing built-in network {name}"))
+    };
+    DeployedNetworks {
+        arbitrum_sepolia: network("arbitrum-sepolia"),
+        arbitrum_one: network("arbitrum-one"),
+        base: network("base"),
+    }
+});
+
+/// The chain IDs of the built-in networks, for config files without them.
+const KNOWN_CHAIN_IDS: [(&str, u64); 3] = [
+    ("arbitrum-sepolia", 421614),
+    ("arbitrum-one", 42161),
+    ("base", 8453),
+];
 
-pub static ACCOUNT_MESSAGE_SCOPE: Lazy<[u8; 32]> =
-    Lazy::new(|| keccak256("HANDLE_SYNTH_ACCOUNT_MESSAGE".as_bytes()));
+static ACTIVE_CONFIG: Lazy<RwLock<Arc<Config>>> =
+    Lazy::new(|| RwLock::new(Arc::new(get_build_config())));
 
-pub type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+/// A client signing with `S` through a provider `M`,
+/// which default to a local wallet over HTTP.
+pub type Client<S = LocalWallet, M = Provider<Http>> = SignerMiddleware<M, S>;
 
 #[derive(Debug, Clone)]
-pub struct Contracts {
-    pub account: Account<Client>,
-    pub liquidity_pool: LiquidityPool<Client>,
-    pub treasury: Treasury<Client>,
+pub struct Contracts<M = Client> {
+    pub account: Account<M>,
+    pub beacon: Beacon<M>,
+    pub liquidity_pool: LiquidityPool<M>,
+    pub treasury: Treasury<M>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+/// The network configs, keyed by lowercase network name.
+/// Each network must have a unique chain ID.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(
+    try_from = "HashMap<String, NetworkConfig>",
+    into = "HashMap<String, NetworkConfig>"
+)]
 pub struct Config {
+    networks: HashMap<String, NetworkConfig>,
+}
+
+/// The built-in networks, as fields.
+#[derive(Debug, Clone)]
+pub struct DeployedNetworks {
     pub arbitrum_sepolia: NetworkConfig,
     pub arbitrum_one: NetworkConfig,
     pub base: NetworkConfig,
@@ -39,6 +85,10 @@ pub struct Config {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NetworkConfig {
+    /// Defaults to the chain ID of the built-in network of the same name,
+    /// for config files from before it was required.
+    #[serde(default)]
+    pub chain_id: u64,
     pub ws: String,
     pub subgraph: String,
     /// The fxUSD (or equivalent, e.g. mock USD for testnet) token.
@@ -50,74 +100,403 @@ pub struct NetworkConfig {
     pub liquidity_pool: String,
 }
 
+/// Builds a [Config] from any combination of the built-in networks,
+/// config files, environment variables and custom networks.
+/// Later sources override networks of the same name, and fail if they add
+/// a network with the chain ID of another network.
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid config: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("missing environment variable {0}")]
+    MissingEnv(String),
+    #[error("invalid environment variable {name}: {value}")]
+    InvalidEnv { name: String, value: String },
+    #[error("networks {existing} and {name} have the same chain ID {chain_id}")]
+    DuplicateChainId {
+        chain_id: u64,
+        existing: String,
+        name: String,
+    },
+    #[error("missing chain ID for network {0}")]
+    MissingChainId(String),
+}
+
 impl Config {
+    /// Returns the built-in config for the deployed networks.
     pub fn from_build_json() -> serde_json::Result<Self> {
-        Ok(serde_json::from_str::<Self>(include_str!(
-            "../config.json"
-        ))?)
+        Self::from_json(include_str!("../config.json"))
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str::<Self>(json)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Ok(Self::from_json(&fs::read_to_string(path)?)?)
+    }
+
+    /// Returns the built-in config, with the networks of the file at
+    /// [CONFIG_PATH_ENV] and the network defined by the
+    /// [NETWORK_ENV_PREFIX] variables if set.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(Self::builder().with_defaults()?.with_env()?.build())
+    }
+
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    pub fn network(&self, name: &str) -> Option<&NetworkConfig> {
+        self.networks.get(&name.to_lowercase())
+    }
+
+    /// Returns the name and config of the network with the chain ID.
+    pub fn network_by_chain_id(&self, chain_id: u64) -> Option<(&str, &NetworkConfig)> {
+        self.networks
+            .iter()
+            .find(|(_, config)| config.chain_id == chain_id)
+            .map(|(name, config)| (name.as_str(), config))
+    }
+
+    /// Adds or replaces a network, failing if another network has the
+    /// same chain ID.
+    /// A missing (zero) chain ID is inferred for the built-in network names.
+    pub fn insert_network(
+        &mut self,
+        name: &str,
+        mut config: NetworkConfig,
+    ) -> Result<(), ConfigError> {
+        let name = name.to_lowercase();
+        if config.chain_id == 0 {
+            config.chain_id = KNOWN_CHAIN_IDS
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, chain_id)| *chain_id)
+                .ok_or_else(|| ConfigError::MissingChainId(name.clone()))?;
+        }
+        if let Some((existing, _)) = self.network_by_chain_id(config.chain_id) {
+            if existing != name {
+                return Err(ConfigError::DuplicateChainId {
+                    chain_id: config.chain_id,
+                    existing: existing.to_owned(),
+                    name,
+                });
+            }
+        }
+        self.networks.insert(name, config);
+        Ok(())
+    }
+
+    /// Adds or replaces the networks of the other config.
+    pub fn merge(&mut self, other: Config) -> Result<(), ConfigError> {
+        for (name, network) in other {
+            self.insert_network(&name, network)?;
+        }
+        Ok(())
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = (&str, &NetworkConfig)> {
+        self.networks
+            .iter()
+            .map(|(name, config)| (name.as_str(), config))
+    }
+}
+
+impl IntoIterator for Config {
+    type Item = (String, NetworkConfig);
+    type IntoIter = std::collections::hash_map::IntoIter<String, NetworkConfig>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.networks.into_iter()
+    }
+}
+
+impl TryFrom<HashMap<String, NetworkConfig>> for Config {
+    type Error = ConfigError;
+
+    fn try_from(networks: HashMap<String, NetworkConfig>) -> Result<Self, Self::Error> {
+        let mut config = Config::default();
+        for (name, network) in networks {
+            config.insert_network(&name, network)?;
+        }
+        Ok(config)
+    }
+}
+
+impl From<Config> for HashMap<String, NetworkConfig> {
+    fn from(config: Config) -> Self {
+        config.networks
+    }
+}
+
+impl ConfigBuilder {
+    /// Adds the built-in networks.
+    pub fn with_defaults(mut self) -> Result<Self, ConfigError> {
+        self.config.merge(Config::from_build_json()?)?;
+        Ok(self)
+    }
+
+    pub fn with_file(mut self, path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        self.config.merge(Config::from_file(path)?)?;
+        Ok(self)
+    }
+
+    /// Adds the networks of the file at [CONFIG_PATH_ENV], if set,
+    /// and the network of [ConfigBuilder::with_env_network].
+    pub fn with_env(mut self) -> Result<Self, ConfigError> {
+        if let Ok(path) = env::var(CONFIG_PATH_ENV) {
+            self = self.with_file(path)?;
+        }
+        self.with_env_network()
+    }
+
+    /// Adds the network defined by the [NETWORK_ENV_PREFIX] variables,
+    /// if `HANDLE_SYNTHS_NETWORK_CHAIN_ID` is set.
+    pub fn with_env_network(mut self) -> Result<Self, ConfigError> {
+        if env::var(format!("{NETWORK_ENV_PREFIX}_CHAIN_ID")).is_ok() {
+            let name = env::var(format!("{NETWORK_ENV_PREFIX}_NAME"))
+                .unwrap_or_else(|_| "custom".to_owned());
+            self.config
+                .insert_network(&name, NetworkConfig::from_env(NETWORK_ENV_PREFIX)?)?;
+        }
+        Ok(self)
+    }
+
+    pub fn network(mut self, name: &str, config: NetworkConfig) -> Result<Self, ConfigError> {
+        self.config.insert_network(name, config)?;
+        Ok(self)
+    }
+
+    pub fn build(self) -> Config {
+        self.config
     }
 }
 
-impl Contracts {
-    pub async fn connect(signer: LocalWallet, rpc_url: &str) -> eyre::Result<Self> {
+impl NetworkConfig {
+    /// Reads the network config from the `{prefix}_{FIELD}` environment
+    /// variables, e.g. `{prefix}_CHAIN_ID` and `{prefix}_LIQUIDITY_POOL`.
+    pub fn from_env(prefix: &str) -> Result<Self, ConfigError> {
+        let var = |field: &str| get_env_var(prefix, field);
+        let chain_id = parse_env_var(prefix, "CHAIN_ID")?;
+        let usd = parse_env_var(prefix, "USD")?;
+        Ok(Self {
+            chain_id,
+            ws: var("WS")?,
+            subgraph: var("SUBGRAPH")?,
+            usd,
+            beacon: var("BEACON")?,
+            account: var("ACCOUNT")?,
+            treasury: var("TREASURY")?,
+            liquidity_token_factory: var("LIQUIDITY_TOKEN_FACTORY")?,
+            liquidity_pool: var("LIQUIDITY_POOL")?,
+        })
+    }
+}
+
+impl<S: Signer + 'static> Contracts<Client<S>> {
+    pub async fn connect(signer: S, rpc_url: &str) -> Result<Self, ClientError> {
         let provider = connect_provider(rpc_url).await?;
-        let chain_id = provider.get_chainid().await?.as_u64();
+        let client = get_client(provider, signer).await?;
+        Self::from_client(client).await
+    }
+}
+
+impl<M: Middleware + 'static> Contracts<M> {
+    /// Connects to the contracts of the client's network.
+    pub async fn from_client(client: Arc<M>) -> Result<Self, ClientError> {
+        let chain_id = client
+            .get_chainid()
+            .await
+            .map_err(|e| ClientError::Rpc(Box::new(e)))?
+            .as_u64();
         let Some(config) = get_network_config_by_chain_id(chain_id) else {
-            return Err(eyre!("invalid network"));
+            return Err(ClientError::UnsupportedNetwork(chain_id));
         };
-        let client = get_client(provider, signer).await?;
+        Self::from_network_config(&config, client)
+    }
+
+    pub fn from_network_config(
+        config: &NetworkConfig,
+        client: Arc<M>,
+    ) -> Result<Self, ClientError> {
         Ok(Self {
-            account: Account::new(Address::from_str(&config.account)?, client.clone()),
+            account: Account::new(parse_address(&config.account)?, client.clone()),
+            beacon: Beacon::new(parse_address(&config.beacon)?, client.clone()),
             liquidity_pool: LiquidityPool::new(
-                Address::from_str(&config.liquidity_pool)?,
+                parse_address(&config.liquidity_pool)?,
                 client.clone(),
             ),
-            treasury: Treasury::new(Address::from_str(&config.treasury)?, client),
+            treasury: Treasury::new(parse_address(&config.treasury)?, client),
         })
     }
 }
 
-pub async fn connect_websocket(url: Url) -> (WsMessageSender, WsMessageReceiver) {
-    let (tx, rx) = connect_persistent_websocket_async(url)
+pub async fn connect_websocket(
+    url: Url,
+) -> Result<(WsMessageSender, WsMessageReceiver), ClientError> {
+    connect_persistent_websocket_async(url)
         .await
-        .expect("failed to connect");
-    (tx, rx)
+        .map_err(|error| ClientError::WebSocket(error.to_string()))
+}
+
+/// Returns the built-in config, or an empty config if it is invalid,
+/// so that custom networks can still be used.
+fn get_build_config() -> Config {
+    Config::from_build_json().unwrap_or_else(|error| {
+        log::error!("invalid built-in config: {error}");
+        Config::default()
+    })
+}
+
+fn get_env_var(prefix: &str, field: &str) -> Result<String, ConfigError> {
+    let name = format!("{prefix}_{field}");
+    env::var(&name).map_err(|_| ConfigError::MissingEnv(name))
+}
+
+fn parse_env_var<T: FromStr>(prefix: &str, field: &str) -> Result<T, ConfigError> {
+    let value = get_env_var(prefix, field)?;
+    value.parse().map_err(|_| ConfigError::InvalidEnv {
+        name: format!("{prefix}_{field}"),
+        value,
+    })
+}
+
+/// Returns the active config.
+pub fn get_config() -> Arc<Config> {
+    ACTIVE_CONFIG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Replaces the active config, which defaults to [Config::from_build_json].
+pub fn set_config(config: Config) {
+    *ACTIVE_CONFIG
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
 }
 
 pub fn get_network_config(network: &str) -> Option<NetworkConfig> {
-    match network.to_lowercase().as_str() {
-        "arbitrum-one" => Some(CONFIG.arbitrum_one.clone()),
-        "arbitrum-sepolia" => Some(CONFIG.arbitrum_sepolia.clone()),
-        "base" => Some(CONFIG.base.clone()),
-        _ => None,
-    }
+    get_config().network(network).cloned()
 }
 
 pub fn get_network_config_by_chain_id(chain_id: u64) -> Option<NetworkConfig> {
-    let Some(network_name) = chain_id_to_network_name(chain_id) else {
-        return None;
-    };
-    get_network_config(&network_name)
+    get_config()
+        .network_by_chain_id(chain_id)
+        .map(|(_, config)| config.clone())
 }
 
 pub fn chain_id_to_network_name(chain_id: u64) -> Option<String> {
-    match chain_id {
-        42161 => Some("arbitrum-one".to_owned()),
-        421614 => Some("arbitrum-sepolia".to_owned()),
-        8453 => Some("base".to_owned()),
-        _ => None,
-    }
+    get_config()
+        .network_by_chain_id(chain_id)
+        .map(|(name, _)| name.to_owned())
 }
 
-pub async fn get_client(
-    provider: Provider<Http>,
-    wallet: LocalWallet,
-) -> Result<Arc<Client>, SignerMiddlewareError<Provider<Http>, LocalWallet>> {
+/// Returns a client for the signer, with the signer's chain ID set to
+/// that of the provider.
+pub async fn get_client<S: Signer + 'static, M: Middleware + 'static>(
+    provider: M,
+    signer: S,
+) -> Result<Arc<Client<S, M>>, ClientError> {
     Ok(Arc::new(
-        SignerMiddleware::new_with_provider_chain(provider, wallet).await?,
+        SignerMiddleware::new_with_provider_chain(provider, signer).await?,
     ))
 }
 
-pub async fn connect_provider(rpc_url: &str) -> eyre::Result<Provider<Http>> {
+pub async fn connect_provider(rpc_url: &str) -> Result<Provider<Http>, ClientError> {
     Ok(Provider::<Http>::try_connect(rpc_url).await?)
 }
+
+pub(crate) fn parse_address(address: &str) -> Result<Address, ClientError> {
+    Address::from_str(address)
+        .map_err(|_| ClientError::InvalidArgument(format!("invalid address {address}")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn custom_network() {
+        let default = Config::from_build_json().unwrap();
+        let (name, _) = default.network_by_chain_id(42161).unwrap();
+        assert_eq!(name, "arbitrum-one");
+        // Given a local devnet that replaces the arbitrum one deployment;
+        let devnet = NetworkConfig {
+            chain_id: 31337,
+            ws: "ws://localhost:8080".to_owned(),
+            ..default.network("arbitrum-one").unwrap().clone()
+        };
+        let config = Config::builder()
+            .with_defaults()
+            .unwrap()
+            .network("Devnet", devnet)
+            .unwrap()
+            .build();
+        // Then it should be found by name and chain ID;
+        assert_eq!(config.network("devnet").unwrap().chain_id, 31337);
+        let (name, network) = config.network_by_chain_id(31337).unwrap();
+        assert_eq!(name, "devnet");
+        assert_eq!(network.ws, "ws://localhost:8080");
+        assert!(config.network("base").is_some());
+    }
+
+    #[test]
+    fn duplicate_chain_id() {
+        let mut config = Config::from_build_json().unwrap();
+        let arbitrum_one = config.network("arbitrum-one").unwrap().clone();
+        // When a network is added with the chain ID of another network;
+        let result = config.insert_network("arbitrum", arbitrum_one.clone());
+        // Then it should be rejected;
+        assert!(matches!(
+            result,
+            Err(ConfigError::DuplicateChainId {
+                chain_id: 42161,
+                ..
+            })
+        ));
+        // But replacing the network of the same name should be allowed;
+        config.insert_network("Arbitrum-One", arbitrum_one).unwrap();
+        // And config files with duplicate chain IDs should be rejected.
+        let mut networks: HashMap<String, NetworkConfig> = config.clone().into();
+        networks.insert("arbitrum".to_owned(), networks["arbitrum-one"].clone());
+        let json = serde_json::to_string(&networks).unwrap();
+        assert!(Config::from_json(&json).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_config() {
+        assert_eq!(CONFIG.arbitrum_one.chain_id, 42161);
+        assert_eq!(CONFIG.arbitrum_sepolia.chain_id, 421614);
+        assert_eq!(CONFIG.base.chain_id, 8453);
+    }
+
+    #[test]
+    fn missing_chain_id() {
+        let mut json = serde_json::to_value(Config::from_build_json().unwrap()).unwrap();
+        for network in json.as_object_mut().unwrap().values_mut() {
+            network.as_object_mut().unwrap().remove("chainId");
+        }
+        // Given a config file from before chain IDs were required;
+        let config = Config::from_json(&json.to_string()).unwrap();
+        // Then the chain IDs of the built-in networks should be inferred;
+        assert_eq!(config.network("arbitrum-one").unwrap().chain_id, 42161);
+        assert_eq!(config.network("arbitrum-sepolia").unwrap().chain_id, 421614);
+        assert_eq!(config.network("base").unwrap().chain_id, 8453);
+        // But other networks should require one.
+        let mut networks = json.as_object().unwrap().clone();
+        let devnet = networks["base"].clone();
+        networks.insert("devnet".to_owned(), devnet);
+        let json = serde_json::Value::Object(networks).to_string();
+        assert!(Config::from_json(&json).is_err());
+    }
+}
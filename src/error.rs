@@ -0,0 +1,137 @@
+use crate::interface::error::{ErrorCode, ResponseError};
+use crate::interface::pair::params::LpParamError;
+use crate::interface::signing::OrderSignatureError;
+use crate::interface::system_params::SystemParamError;
+use crate::interface::ResponseContent;
+use crate::keys::KeyError;
+use crate::risk::validation::OrderRejection;
+use ethers::middleware::signer::SignerMiddlewareError;
+use ethers::prelude::{Bytes, ContractError, Middleware, ProviderError, Signer};
+use std::error::Error as StdError;
+use std::time::Duration;
+use thiserror::Error;
+use tokio_tungstenite::tungstenite;
+
+/// An error from the client, categorised so that callers can branch on
+/// the kind of failure.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("transport error: {0}")]
+    Transport(Box<tungstenite::Error>),
+    /// The persistent WebSocket could not be connected.
+    #[error("websocket error: {0}")]
+    WebSocket(String),
+    #[error("request {id} timed out after {timeout:?}")]
+    Timeout { id: String, timeout: Duration },
+    #[error("connection lost before receiving a response")]
+    ConnectionLost,
+    #[error("subscription closed")]
+    SubscriptionClosed,
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    /// The server rejected the request.
+    #[error("request rejected: {0}")]
+    Rejected(#[from] ResponseError),
+    /// The server responded with content other than what was expected.
+    #[error("expected {expected}, received {received:#?}")]
+    UnexpectedResponse {
+        expected: &'static str,
+        received: Box<ResponseContent>,
+    },
+    #[error("signing error: {0}")]
+    Signing(#[source] Box<dyn StdError + Send + Sync>),
+    #[error("order signature error: {0}")]
+    OrderSignature(#[from] OrderSignatureError),
+    #[error("LP param error: {0}")]
+    LpParam(#[from] LpParamError),
+    #[error("system param error: {0}")]
+    SystemParam(#[from] SystemParamError),
+    /// The order failed pre-trade validation and was not sent.
+    #[error("order rejected: {0}")]
+    OrderRejected(#[from] OrderRejection),
+    #[error("key error: {0}")]
+    Key(#[from] KeyError),
+    /// A contract call reverted, with the decoded reason if available.
+    #[error("contract reverted: {}", reason.as_deref().unwrap_or("no reason"))]
+    Revert { reason: Option<String>, data: Bytes },
+    /// An RPC or middleware error.
+    #[error("rpc error: {0}")]
+    Rpc(#[source] Box<dyn StdError + Send + Sync>),
+    #[error("no network config for chain ID {0}")]
+    UnsupportedNetwork(u64),
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+}
+
+impl ClientError {
+    pub fn unexpected_response(expected: &'static str, received: ResponseContent) -> Self {
+        Self::UnexpectedResponse {
+            expected,
+            received: Box::new(received),
+        }
+    }
+
+    pub fn signing(error: impl StdError + Send + Sync + 'static) -> Self {
+        Self::Signing(Box::new(error))
+    }
+
+    /// Returns the server error code if the request was rejected.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Rejected(error) => Some(error.code),
+            _ => None,
+        }
+    }
+
+    /// Whether the request may succeed if retried, e.g. after reconnecting.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Transport(_)
+                | Self::Timeout { .. }
+                | Self::ConnectionLost
+                | Self::Rejected(ResponseError {
+                    code: ErrorCode::RateLimited,
+                    ..
+                })
+        )
+    }
+}
+
+impl From<tungstenite::Error> for ClientError {
+    fn from(error: tungstenite::Error) -> Self {
+        Self::Transport(Box::new(error))
+    }
+}
+
+impl<M: Middleware + 'static> From<ContractError<M>> for ClientError {
+    fn from(error: ContractError<M>) -> Self {
+        if let Some(data) = error.as_revert() {
+            return Self::Revert {
+                reason: error.decode_revert::<String>(),
+                data: data.clone(),
+            };
+        }
+        Self::Rpc(Box::new(error))
+    }
+}
+
+impl<M: Middleware + 'static, S: Signer + 'static> From<SignerMiddlewareError<M, S>>
+    for ClientError
+{
+    fn from(error: SignerMiddlewareError<M, S>) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
+
+impl From<url::ParseError> for ClientError {
+    fn from(error: url::ParseError) -> Self {
+        Self::InvalidArgument(format!("invalid URL: {error}"))
+    }
+}
+
+impl From<ProviderError> for ClientError {
+    fn from(error: ProviderError) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+/// An error response to a request, similar to a JSON-RPC error object.
+/// Errors without a known code or data are (de)serialised as plain strings,
+/// for compatibility with string errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+/// A machine-readable error code.
+/// Codes not known to this version deserialise as [ErrorCode::Unknown].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    InvalidRequest,
+    InvalidSignature,
+    NonceReused,
+    Unauthorized,
+    InsufficientMargin,
+    InsufficientBalance,
+    MarketReduceOnly,
+    PairInactive,
+    OrderNotFound,
+    OrderExpired,
+    RateLimited,
+    Internal,
+    #[serde(other)]
+    #[default]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ResponseErrorObject {
+    /// Missing or non-string codes, e.g. JSON-RPC integer codes,
+    /// deserialise as [ErrorCode::Unknown].
+    #[serde(default, deserialize_with = "deserialize_error_code")]
+    code: ErrorCode,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    data: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResponseErrorRepr {
+    Message(String),
+    Object(ResponseErrorObject),
+}
+
+impl ResponseError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Creates an error with no known code, e.g. from a plain string error.
+    pub fn unknown(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unknown, message)
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Deserialises the error data, if any.
+    pub fn parse_data<T: for<'de> Deserialize<'de>>(&self) -> Option<serde_json::Result<T>> {
+        self.data.clone().map(serde_json::from_value)
+    }
+}
+
+impl Display for ResponseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            ErrorCode::Unknown => write!(f, "{}", self.message),
+            code => write!(f, "{code:?}: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
+impl From<String> for ResponseError {
+    fn from(message: String) -> Self {
+        Self::unknown(message)
+    }
+}
+
+impl From<&str> for ResponseError {
+    fn from(message: &str) -> Self {
+        Self::unknown(message)
+    }
+}
+
+impl Serialize for ResponseError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.code == ErrorCode::Unknown && self.data.is_none() {
+            return serializer.serialize_str(&self.message);
+        }
+        ResponseErrorObject {
+            code: self.code,
+            message: self.message.clone(),
+            data: self.data.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ResponseError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match ResponseErrorRepr::deserialize(deserializer)? {
+            ResponseErrorRepr::Message(message) => Self::unknown(message),
+            ResponseErrorRepr::Object(object) => Self {
+                code: object.code,
+                message: object.message,
+                data: object.data,
+            },
+        })
+    }
+}
+
+fn deserialize_error_code<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<ErrorCode, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        value @ Value::String(_) => serde_json::from_value(value).unwrap_or_default(),
+        _ => ErrorCode::Unknown,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn string_and_object_errors() {
+        let error: ResponseError = serde_json::from_value(json!("order not found")).unwrap();
+        assert_eq!(error, ResponseError::unknown("order not found"));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!("order not found")
+        );
+        let error: ResponseError = serde_json::from_value(json!({
+            "code": "insufficientMargin",
+            "message": "insufficient margin",
+            "data": { "freeMargin": "10" },
+        }))
+        .unwrap();
+        assert_eq!(error.code, ErrorCode::InsufficientMargin);
+        assert_eq!(error.data, Some(json!({ "freeMargin": "10" })));
+        // Codes from newer servers are not rejected.
+        let error: ResponseError =
+            serde_json::from_value(json!({ "code": "someNewCode", "message": "new" })).unwrap();
+        assert_eq!(error.code, ErrorCode::Unknown);
+        // JSON-RPC integer codes and missing codes are unknown.
+        let error: ResponseError =
+            serde_json::from_value(json!({ "code": -32600, "message": "invalid" })).unwrap();
+        assert_eq!(error, ResponseError::unknown("invalid"));
+        let error: ResponseError = serde_json::from_value(json!({ "message": "none" })).unwrap();
+        assert_eq!(error, ResponseError::unknown("none"));
+    }
+}
@@ -204,6 +204,19 @@ pub struct LpParamEffectFunding {
     pub timestamp_unix_millis: i64,
 }
 
+impl WithdrawEvent {
+    /// Returns whether the withdrawal included LP profits, i.e. the user
+    /// has withdrawn all of their initial deposit.
+    pub fn has_withdrawn_lp_profits(&self) -> bool {
+        self.lp_profits_withdrawn.has_withdrawals()
+    }
+
+    /// Returns the total LP profits withdrawn across all LPs.
+    pub fn total_lp_profits_withdrawn(&self) -> BigDecimal {
+        self.lp_profits_withdrawn.total()
+    }
+}
+
 pub type SetSystemParamEvent = SetSystemParamRequest;
 
 pub type ClearSystemParamEvent = ClearSystemParamRequest;
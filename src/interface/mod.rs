@@ -3,6 +3,7 @@
 //! The only difference between JSON-RPC 2.0 is that the "jsonrpc"
 //! string is not included in messages.
 //! Read about JSON-RPC here: https://www.jsonrpc.org/specification
+use crate::interface::error::ResponseError;
 use crate::interface::events::{Event, TradeEvent};
 use crate::interface::liquidity_pool::LiquidityPoolId;
 use crate::interface::order::Order;
@@ -18,11 +19,15 @@ use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 
 pub mod contract_types;
+pub mod error;
 pub mod events;
 pub mod liquidity_pool;
 pub mod order;
 pub mod pair;
+mod param_encoding;
 pub mod requests;
+pub mod signing;
+pub mod system_params;
 
 pub const PRICE_DECIMALS: i64 = 8;
 pub const AMOUNT_DECIMALS: i64 = 18;
@@ -84,7 +89,7 @@ pub struct Response {
     /// For all other requests, this is the same as the request ID.
     pub id: Option<MessageId>,
     #[serde(flatten)]
-    pub content: ResponseResult<ResponseContent, String>,
+    pub content: ResponseResult<ResponseContent, ResponseError>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -136,36 +141,44 @@ impl<T, E> From<Result<T, E>> for ResponseResult<T, E> {
 }
 
 impl Response {
+    /// Creates a response from any error type, which is formatted as an
+    /// error with an unknown code.
     pub fn from<T>(response: Result<ResponseContent, T>, id: Option<MessageId>) -> Self
     where
         T: Debug,
     {
-        let result: Result<ResponseContent, String> = match response {
-            Ok(result) => Ok(result),
-            Err(error) => Err(format!("{:#?}", error)),
-        };
+        let result = response.map_err(|error| ResponseError::unknown(format!("{:#?}", error)));
+        Self::from_result(result, id)
+    }
+
+    pub fn from_result(
+        response: Result<ResponseContent, ResponseError>,
+        id: Option<MessageId>,
+    ) -> Self {
         Self {
             id,
-            content: result.into(),
+            content: response.into(),
         }
     }
 
-    pub fn take(self) -> (Result<ResponseContent, String>, Option<String>) {
+    pub fn take(self) -> (Result<ResponseContent, ResponseError>, Option<String>) {
         if let Some(result) = self.content.result {
             (Ok(result), self.id)
         } else if let Some(error) = self.content.error {
             (Err(error), self.id)
         } else {
-            (Err("invalid response".to_string()), self.id)
+            (Err(ResponseError::unknown("invalid response")), self.id)
         }
     }
 
-    pub fn content(self) -> Result<ResponseContent, String> {
+    pub fn content(self) -> Result<ResponseContent, ResponseError> {
         if let Some(error) = self.content.error {
             return Err(error);
         };
         let Some(content) = self.content.result else {
-            return Err("no response content or error received".to_owned());
+            return Err(ResponseError::unknown(
+                "no response content or error received",
+            ));
         };
         Ok(content)
     }
@@ -207,6 +220,25 @@ pub enum AccountRole {
     ProtocolAdmin,
 }
 
+impl AccountRole {
+    /// The roles that may be granted to or revoked from an account user.
+    pub const ACCOUNT_USER_ROLES: [AccountRole; 4] = [
+        AccountRole::Owner,
+        AccountRole::Trader,
+        AccountRole::Withdraw,
+        AccountRole::Deposit,
+    ];
+}
+
+/// A role of a liquidity pool user, validated by the liquidity pool
+/// contract in the same way as account roles.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum LiquidityPoolRole {
+    _None,
+    Owner,
+}
+
 pub type AccountId = u64;
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -254,3 +286,26 @@ pub struct PositionSnapshot {
 pub struct LpProfitsWithdrawnSnapshot(pub LpIdMap<BigDecimal>);
 
 pub type LpIdMap<T> = HashMap<LiquidityPoolId, T>;
+
+impl LpProfitsWithdrawnSnapshot {
+    /// Returns the profit withdrawn from the given LP, or zero if none.
+    pub fn get(&self, lp_id: &LiquidityPoolId) -> BigDecimal {
+        self.0.get(lp_id).cloned().unwrap_or_default()
+    }
+
+    /// Returns the total profit withdrawn across all LPs.
+    pub fn total(&self) -> BigDecimal {
+        self.0.values().sum()
+    }
+
+    /// Returns whether a non-zero LP profit amount was withdrawn from any LP.
+    pub fn has_withdrawals(&self) -> bool {
+        self.0
+            .values()
+            .any(|amount| *amount != BigDecimal::default())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&LiquidityPoolId, &BigDecimal)> {
+        self.0.iter()
+    }
+}
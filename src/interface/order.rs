@@ -168,18 +168,18 @@ pub enum OrderCancellationReason {
     LinkedOrder,
 }
 
-fn now_unix_millis() -> i64 {
+pub(crate) fn now_unix_millis() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis() as i64
 }
 
-fn default_expiry_unix_millis() -> i64 {
+pub(crate) fn default_expiry_unix_millis() -> i64 {
     now_unix_millis() + (DEFAULT_EXPIRY_MONTHS * 31 * 24 * 60 * 60 * 1_000)
 }
 
-fn new_order_id() -> Uuid {
+pub(crate) fn new_order_id() -> Uuid {
     Uuid::now_v7()
 }
 
@@ -327,3 +327,70 @@ impl LinkedOrderKind {
         }
     }
 }
+
+/// Builds unsigned orders for tests, defaulting to a market order for
+/// one ETH/USD lot in LP 1 by account 1.
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub(crate) struct TestOrderBuilder {
+    order: Order,
+}
+
+#[cfg(test)]
+impl Default for TestOrderBuilder {
+    fn default() -> Self {
+        Self {
+            order: Order {
+                id: new_order_id(),
+                account_id: 1,
+                lp_id: LiquidityPoolId::new(ethers::prelude::U256::from(1)),
+                size: TradeSize::Lot(BigDecimal::from(1)),
+                pair: Pair::new("ETH", "USD").unwrap(),
+                kind: OrderKind::Market,
+                status: OrderStatus::default(),
+                account_user: Address::zero(),
+                signature: Bytes::default(),
+                nonce: Uuid::new_v4(),
+                created_timestamp_unix_millis: 0,
+                expiry_timestamp_unix_millis: 0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+impl TestOrderBuilder {
+    pub fn account_id(mut self, account_id: AccountId) -> Self {
+        self.order.account_id = account_id;
+        self
+    }
+
+    pub fn lp_id(mut self, lp_id: LiquidityPoolId) -> Self {
+        self.order.lp_id = lp_id;
+        self
+    }
+
+    pub fn size(mut self, size: TradeSize) -> Self {
+        self.order.size = size;
+        self
+    }
+
+    pub fn kind(mut self, kind: OrderKind) -> Self {
+        self.order.kind = kind;
+        self
+    }
+
+    pub fn account_user(mut self, account_user: Address) -> Self {
+        self.order.account_user = account_user;
+        self
+    }
+
+    pub fn expiry_timestamp_unix_millis(mut self, expiry_timestamp_unix_millis: i64) -> Self {
+        self.order.expiry_timestamp_unix_millis = expiry_timestamp_unix_millis;
+        self
+    }
+
+    pub fn build(self) -> Order {
+        self.order
+    }
+}
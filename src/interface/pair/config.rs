@@ -1,7 +1,12 @@
+use crate::interface::events::LpConfigUpdateEvent;
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::pair::Pair;
+use crate::interface::LpPair;
 use bigdecimal::BigDecimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase", default)]
 pub struct PairConfig {
     pub initial_margin_fraction: BigDecimal,
@@ -31,3 +36,63 @@ pub struct PairConfig {
     pub price_impact_fraction: Option<BigDecimal>,
     pub skew_scale: Option<BigDecimal>,
 }
+
+/// The configs of LP pairs, e.g. from the [Event::LpConfigUpdate] snapshot
+/// returned for [crate::interface::RequestContent::GetLpConfig].
+///
+/// [Event::LpConfigUpdate]: crate::interface::events::Event::LpConfigUpdate
+#[derive(Clone, Default, Debug)]
+pub struct LpConfig(HashMap<LpPair, PairConfig>);
+
+impl LpConfig {
+    pub fn new(configs: HashMap<LpPair, PairConfig>) -> Self {
+        Self(configs)
+    }
+
+    pub fn get(&self, lp_pair: &LpPair) -> Option<&PairConfig> {
+        self.0.get(lp_pair)
+    }
+
+    /// Sets the config of the LP pair, returning the previous config.
+    pub fn insert(&mut self, lp_pair: LpPair, config: PairConfig) -> Option<PairConfig> {
+        self.0.insert(lp_pair, config)
+    }
+
+    /// Returns the configs of the pairs in the LP.
+    pub fn get_lp_configs(
+        &self,
+        lp_id: LiquidityPoolId,
+    ) -> impl Iterator<Item = (&Pair, &PairConfig)> {
+        self.0
+            .iter()
+            .filter(move |(lp_pair, _)| lp_pair.lp_id == lp_id)
+            .map(|(lp_pair, config)| (&lp_pair.pair, config))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&LpPair, &PairConfig)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_inner(self) -> HashMap<LpPair, PairConfig> {
+        self.0
+    }
+}
+
+impl From<Vec<LpConfigUpdateEvent>> for LpConfig {
+    fn from(updates: Vec<LpConfigUpdateEvent>) -> Self {
+        Self(
+            updates
+                .into_iter()
+                .map(|update| (LpPair::from((update.lp_id, update.pair)), update.config))
+                .collect(),
+        )
+    }
+}
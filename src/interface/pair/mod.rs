@@ -10,6 +10,7 @@ use std::{
 use thiserror::Error;
 
 pub mod config;
+pub mod params;
 
 pub const MAX_SYMBOL_LEN: usize = 16;
 
@@ -0,0 +1,413 @@
+//! The LP params that can be set for a pair, one for each field of
+//! [PairConfig].
+//! Params are identified by their name and pair as given by an
+//! [LpParamIdFormat]. Neither the contracts nor the request types define how
+//! pair params are keyed, so the default format, `{name}:{pair}`
+//! (e.g. `isActive:ETH/USD`), is an unverified guess and should be replaced
+//! by the trade server's if it differs. Params can also be set from their
+//! raw ID and value with [crate::lp_admin::LpAdminClient::set_lp_param].
+//! Values are encoded as a single 32 byte ABI word, since they are
+//! set in the liquidity pool contract as `bytes32`:
+//! - decimals as `int256` fixed point integers with [LP_PARAM_DECIMALS]
+//! - booleans and integers as `uint256`.
+//!
+//! Unset optional values cannot be encoded, as `setParam` has no way of
+//! clearing a param and any `bytes32` value would be read as a limit.
+//! Empty values, e.g. from the trade server, are decoded as unset.
+use crate::interface::pair::config::PairConfig;
+use crate::interface::pair::Pair;
+use crate::interface::param_encoding;
+use bigdecimal::BigDecimal;
+use ethers::prelude::Bytes;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const LP_PARAM_DECIMALS: i64 = 18;
+
+/// A template for LP param IDs, with one `{name}` and one `{pair}`
+/// placeholder separated by a non-empty literal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LpParamIdFormat {
+    template: String,
+}
+
+/// A typed LP param value for a pair.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LpParam {
+    InitialMarginFraction(BigDecimal),
+    MaintenanceMarginFraction(BigDecimal),
+    IncrementalInitialMarginFraction(BigDecimal),
+    BaselinePositionSize(BigDecimal),
+    IncrementalPositionSize(BigDecimal),
+    MarginFeeFraction(BigDecimal),
+    SymmetricalSpreadFraction(BigDecimal),
+    IsActive(bool),
+    IsReduceOnly(bool),
+    MaxOpenInterestDiff(Option<BigDecimal>),
+    MaxOpenInterestLong(Option<BigDecimal>),
+    MaxOpenInterestShort(Option<BigDecimal>),
+    BorrowFeeFactor(BigDecimal),
+    FundingFactor(BigDecimal),
+    FundingExponent(BigDecimal),
+    TradePriceExpiration(Option<u64>),
+    UsePriceImpact(bool),
+    PriceImpactFraction(Option<BigDecimal>),
+    SkewScale(Option<BigDecimal>),
+}
+
+#[derive(Debug, Error)]
+pub enum LpParamError {
+    #[error("unknown LP param {0}")]
+    UnknownParam(String),
+    #[error("invalid LP param ID {0}")]
+    InvalidParamId(String),
+    #[error("invalid value {value} for LP param {name}")]
+    InvalidValue { name: String, value: Bytes },
+    #[error("cannot encode {0} as a fixed point integer")]
+    InvalidDecimal(BigDecimal),
+    #[error("LP param {0} cannot be unset")]
+    UnsetValue(&'static str),
+    #[error("LP param value {0} is not 32 bytes")]
+    InvalidLength(Bytes),
+    #[error("invalid LP param ID format {0}")]
+    InvalidIdFormat(String),
+}
+
+impl LpParamIdFormat {
+    /// The assumed default format, `{name}:{pair}`, which is not confirmed
+    /// by the contracts or the trade server.
+    pub const DEFAULT_TEMPLATE: &'static str = "{name}:{pair}";
+
+    pub fn new(template: &str) -> Result<Self, LpParamError> {
+        let format = Self {
+            template: template.to_owned(),
+        };
+        match format.parts() {
+            Some(_) => Ok(format),
+            None => Err(LpParamError::InvalidIdFormat(template.to_owned())),
+        }
+    }
+
+    /// Returns the ID of the param with the name for the pair.
+    pub fn param_id(&self, name: &str, pair: &Pair) -> String {
+        self.template
+            .replace("{name}", name)
+            .replace("{pair}", &pair.to_string())
+    }
+
+    /// Returns the param name and pair of the ID.
+    pub fn parse<'a>(&self, param_id: &'a str) -> Result<(&'a str, Pair), LpParamError> {
+        let invalid_id = || LpParamError::InvalidParamId(param_id.to_owned());
+        let (prefix, first, separator, suffix) = self.parts().ok_or_else(invalid_id)?;
+        let (left, right) = param_id
+            .strip_prefix(prefix)
+            .and_then(|id| id.strip_suffix(suffix))
+            .and_then(|id| id.split_once(separator))
+            .ok_or_else(invalid_id)?;
+        let (name, pair) = match first {
+            "{name}" => (left, right),
+            _ => (right, left),
+        };
+        let pair = Pair::from_str(pair).map_err(|_| invalid_id())?;
+        Ok((name, pair))
+    }
+
+    /// Splits the template into its prefix, first placeholder,
+    /// separator and suffix.
+    fn parts(&self) -> Option<(&str, &str, &str, &str)> {
+        let name = self.template.find("{name}")?;
+        let pair = self.template.find("{pair}")?;
+        if self.template.matches("{name}").count() != 1
+            || self.template.matches("{pair}").count() != 1
+        {
+            return None;
+        }
+        let ((first, first_index), (second, second_index)) = match name < pair {
+            true => (("{name}", name), ("{pair}", pair)),
+            false => (("{pair}", pair), ("{name}", name)),
+        };
+        let separator = &self.template[first_index + first.len()..second_index];
+        if separator.is_empty() {
+            return None;
+        }
+        Some((
+            &self.template[..first_index],
+            first,
+            separator,
+            &self.template[second_index + second.len()..],
+        ))
+    }
+}
+
+impl Default for LpParamIdFormat {
+    fn default() -> Self {
+        Self {
+            template: Self::DEFAULT_TEMPLATE.to_owned(),
+        }
+    }
+}
+
+/// A param value borrowed from an [LpParam], for encoding.
+enum ParamValue<'a> {
+    Decimal(Option<&'a BigDecimal>),
+    Bool(bool),
+    Integer(Option<u64>),
+}
+
+impl LpParam {
+    /// The param name, which is the camelCase [PairConfig] field name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::InitialMarginFraction(_) => "initialMarginFraction",
+            Self::MaintenanceMarginFraction(_) => "maintenanceMarginFraction",
+            Self::IncrementalInitialMarginFraction(_) => "incrementalInitialMarginFraction",
+            Self::BaselinePositionSize(_) => "baselinePositionSize",
+            Self::IncrementalPositionSize(_) => "incrementalPositionSize",
+            Self::MarginFeeFraction(_) => "marginFeeFraction",
+            Self::SymmetricalSpreadFraction(_) => "symmetricalSpreadFraction",
+            Self::IsActive(_) => "isActive",
+            Self::IsReduceOnly(_) => "isReduceOnly",
+            Self::MaxOpenInterestDiff(_) => "maxOpenInterestDiff",
+            Self::MaxOpenInterestLong(_) => "maxOpenInterestLong",
+            Self::MaxOpenInterestShort(_) => "maxOpenInterestShort",
+            Self::BorrowFeeFactor(_) => "borrowFeeFactor",
+            Self::FundingFactor(_) => "fundingFactor",
+            Self::FundingExponent(_) => "fundingExponent",
+            Self::TradePriceExpiration(_) => "tradePriceExpiration",
+            Self::UsePriceImpact(_) => "usePriceImpact",
+            Self::PriceImpactFraction(_) => "priceImpactFraction",
+            Self::SkewScale(_) => "skewScale",
+        }
+    }
+
+    /// Returns the param ID of this param for the pair in the format.
+    pub fn param_id(&self, pair: &Pair, format: &LpParamIdFormat) -> String {
+        format.param_id(self.name(), pair)
+    }
+
+    /// Encodes the value as a 32 byte ABI word,
+    /// failing with [LpParamError::UnsetValue] if an optional value is unset.
+    pub fn encode(&self) -> Result<Bytes, LpParamError> {
+        Ok(match self.value() {
+            ParamValue::Decimal(None) | ParamValue::Integer(None) => {
+                return Err(LpParamError::UnsetValue(self.name()));
+            }
+            ParamValue::Decimal(Some(value)) => {
+                param_encoding::encode_decimal(value, LP_PARAM_DECIMALS)
+                    .ok_or_else(|| LpParamError::InvalidDecimal(value.clone()))?
+            }
+            ParamValue::Bool(value) => param_encoding::encode_uint(value as u8),
+            ParamValue::Integer(Some(value)) => param_encoding::encode_uint(value),
+        })
+    }
+
+    /// Decodes a param from its ID in the format and its encoded value.
+    pub fn decode(
+        format: &LpParamIdFormat,
+        param_id: &str,
+        value: &Bytes,
+    ) -> Result<(Pair, Self), LpParamError> {
+        let (name, pair) = format.parse(param_id)?;
+        let param = match name {
+            "initialMarginFraction" => Self::InitialMarginFraction(decode_decimal(name, value)?),
+            "maintenanceMarginFraction" => {
+                Self::MaintenanceMarginFraction(decode_decimal(name, value)?)
+            }
+            "incrementalInitialMarginFraction" => {
+                Self::IncrementalInitialMarginFraction(decode_decimal(name, value)?)
+            }
+            "baselinePositionSize" => Self::BaselinePositionSize(decode_decimal(name, value)?),
+            "incrementalPositionSize" => {
+                Self::IncrementalPositionSize(decode_decimal(name, value)?)
+            }
+            "marginFeeFraction" => Self::MarginFeeFraction(decode_decimal(name, value)?),
+            "symmetricalSpreadFraction" => {
+                Self::SymmetricalSpreadFraction(decode_decimal(name, value)?)
+            }
+            "isActive" => Self::IsActive(decode_bool(name, value)?),
+            "isReduceOnly" => Self::IsReduceOnly(decode_bool(name, value)?),
+            "maxOpenInterestDiff" => {
+                Self::MaxOpenInterestDiff(decode_optional(name, value, decode_decimal)?)
+            }
+            "maxOpenInterestLong" => {
+                Self::MaxOpenInterestLong(decode_optional(name, value, decode_decimal)?)
+            }
+            "maxOpenInterestShort" => {
+                Self::MaxOpenInterestShort(decode_optional(name, value, decode_decimal)?)
+            }
+            "borrowFeeFactor" => Self::BorrowFeeFactor(decode_decimal(name, value)?),
+            "fundingFactor" => Self::FundingFactor(decode_decimal(name, value)?),
+            "fundingExponent" => Self::FundingExponent(decode_decimal(name, value)?),
+            "tradePriceExpiration" => {
+                Self::TradePriceExpiration(decode_optional(name, value, decode_integer)?)
+            }
+            "usePriceImpact" => Self::UsePriceImpact(decode_bool(name, value)?),
+            "priceImpactFraction" => {
+                Self::PriceImpactFraction(decode_optional(name, value, decode_decimal)?)
+            }
+            "skewScale" => Self::SkewScale(decode_optional(name, value, decode_decimal)?),
+            name => return Err(LpParamError::UnknownParam(name.to_owned())),
+        };
+        Ok((pair, param))
+    }
+
+    /// Sets the param's field in the config.
+    pub fn apply(self, config: &mut PairConfig) {
+        match self {
+            Self::InitialMarginFraction(v) => config.initial_margin_fraction = v,
+            Self::MaintenanceMarginFraction(v) => config.maintenance_margin_fraction = v,
+            Self::IncrementalInitialMarginFraction(v) => {
+                config.incremental_initial_margin_fraction = v
+            }
+            Self::BaselinePositionSize(v) => config.baseline_position_size = v,
+            Self::IncrementalPositionSize(v) => config.incremental_position_size = v,
+            Self::MarginFeeFraction(v) => config.margin_fee_fraction = v,
+            Self::SymmetricalSpreadFraction(v) => config.symmetrical_spread_fraction = v,
+            Self::IsActive(v) => config.is_active = v,
+            Self::IsReduceOnly(v) => config.is_reduce_only = v,
+            Self::MaxOpenInterestDiff(v) => config.max_open_interest_diff = v,
+            Self::MaxOpenInterestLong(v) => config.max_open_interest_long = v,
+            Self::MaxOpenInterestShort(v) => config.max_open_interest_short = v,
+            Self::BorrowFeeFactor(v) => config.borrow_fee_factor = v,
+            Self::FundingFactor(v) => config.funding_factor = v,
+            Self::FundingExponent(v) => config.funding_exponent = v,
+            Self::TradePriceExpiration(v) => config.trade_price_expiration = v,
+            Self::UsePriceImpact(v) => config.use_price_impact = v,
+            Self::PriceImpactFraction(v) => config.price_impact_fraction = v,
+            Self::SkewScale(v) => config.skew_scale = v,
+        }
+    }
+
+    fn value(&self) -> ParamValue<'_> {
+        match self {
+            Self::InitialMarginFraction(v)
+            | Self::MaintenanceMarginFraction(v)
+            | Self::IncrementalInitialMarginFraction(v)
+            | Self::BaselinePositionSize(v)
+            | Self::IncrementalPositionSize(v)
+            | Self::MarginFeeFraction(v)
+            | Self::SymmetricalSpreadFraction(v)
+            | Self::BorrowFeeFactor(v)
+            | Self::FundingFactor(v)
+            | Self::FundingExponent(v) => ParamValue::Decimal(Some(v)),
+            Self::MaxOpenInterestDiff(v)
+            | Self::MaxOpenInterestLong(v)
+            | Self::MaxOpenInterestShort(v)
+            | Self::PriceImpactFraction(v)
+            | Self::SkewScale(v) => ParamValue::Decimal(v.as_ref()),
+            Self::IsActive(v) | Self::IsReduceOnly(v) | Self::UsePriceImpact(v) => {
+                ParamValue::Bool(*v)
+            }
+            Self::TradePriceExpiration(v) => ParamValue::Integer(*v),
+        }
+    }
+}
+
+fn decode_decimal(name: &str, value: &Bytes) -> Result<BigDecimal, LpParamError> {
+    param_encoding::decode_decimal(value, LP_PARAM_DECIMALS)
+        .ok_or_else(|| invalid_value(name, value))
+}
+
+fn decode_bool(name: &str, value: &Bytes) -> Result<bool, LpParamError> {
+    param_encoding::decode_bool(value).ok_or_else(|| invalid_value(name, value))
+}
+
+fn decode_integer(name: &str, value: &Bytes) -> Result<u64, LpParamError> {
+    param_encoding::decode_u64(value).ok_or_else(|| invalid_value(name, value))
+}
+
+fn decode_optional<T>(
+    name: &str,
+    value: &Bytes,
+    decode: fn(&str, &Bytes) -> Result<T, LpParamError>,
+) -> Result<Option<T>, LpParamError> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    decode(name, value).map(Some)
+}
+
+fn invalid_value(name: &str, value: &Bytes) -> LpParamError {
+    LpParamError::InvalidValue {
+        name: name.to_owned(),
+        value: value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::abi::AbiEncode;
+    use ethers::prelude::U256;
+
+    #[test]
+    fn encode_and_decode() {
+        let pair = Pair::new("ETH", "USD").unwrap();
+        let params = [
+            LpParam::InitialMarginFraction(BigDecimal::from_str("0.05").unwrap()),
+            LpParam::IsReduceOnly(true),
+            LpParam::MaxOpenInterestLong(Some(BigDecimal::from(-2))),
+            LpParam::TradePriceExpiration(Some(30)),
+        ];
+        let format = LpParamIdFormat::default();
+        for param in params {
+            let param_id = param.param_id(&pair, &format);
+            let value = param.encode().unwrap();
+            assert_eq!(
+                LpParam::decode(&format, &param_id, &value).unwrap(),
+                (pair, param)
+            );
+        }
+        // A boolean param must not accept other integers.
+        let value = Bytes::from(U256::from(2).encode());
+        assert!(matches!(
+            LpParam::decode(&format, "isActive:ETH/USD", &value),
+            Err(LpParamError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            LpParam::SkewScale(Some(BigDecimal::from_str("1e-19").unwrap())).encode(),
+            Err(LpParamError::InvalidDecimal(_))
+        ));
+        // Unset values cannot be set, but are decoded from empty values.
+        assert!(matches!(
+            LpParam::MaxOpenInterestShort(None).encode(),
+            Err(LpParamError::UnsetValue("maxOpenInterestShort"))
+        ));
+        assert_eq!(
+            LpParam::decode(&format, "maxOpenInterestShort:ETH/USD", &Bytes::new()).unwrap(),
+            (pair, LpParam::MaxOpenInterestShort(None))
+        );
+    }
+
+    #[test]
+    fn param_id_format() {
+        let pair = Pair::new("ETH", "USD").unwrap();
+        let default = LpParamIdFormat::default();
+        assert_eq!(default.param_id("isActive", &pair), "isActive:ETH/USD");
+        // Given a server that keys params by pair first;
+        let format = LpParamIdFormat::new("pair.{pair}.{name}").unwrap();
+        let param_id = LpParam::IsActive(true).param_id(&pair, &format);
+        // Then IDs should be formed and parsed in that format.
+        assert_eq!(param_id, "pair.ETH/USD.isActive");
+        assert_eq!(format.parse(&param_id).unwrap(), ("isActive", pair));
+        assert!(matches!(
+            default.parse(&param_id),
+            Err(LpParamError::InvalidParamId(_))
+        ));
+        for template in ["{name}", "{name}{pair}", "{name}:{pair}:{pair}"] {
+            assert!(matches!(
+                LpParamIdFormat::new(template),
+                Err(LpParamError::InvalidIdFormat(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn apply_to_config() {
+        let mut config = PairConfig::default();
+        LpParam::IsActive(true).apply(&mut config);
+        LpParam::MaxOpenInterestDiff(Some(BigDecimal::from(10))).apply(&mut config);
+        assert!(config.is_active);
+        assert_eq!(config.max_open_interest_diff, Some(BigDecimal::from(10)));
+    }
+}
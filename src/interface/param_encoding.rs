@@ -0,0 +1,68 @@
+//! Encoding of param values as a single 32 byte ABI word, as they are set
+//! in contracts as `bytes32`.
+//! Decoding returns `None` if the value is not a valid word of the type.
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::BigDecimal;
+use ethers::abi::AbiEncode;
+use ethers::prelude::{Address, Bytes, I256, U256};
+use std::str::FromStr;
+
+/// Encodes the decimal as an `int256` fixed point integer, or returns
+/// `None` if it has more decimal places than allowed.
+pub(crate) fn encode_decimal(value: &BigDecimal, decimals: i64) -> Option<Bytes> {
+    Some(to_fixed_point(value, decimals)?.into_raw().encode().into())
+}
+
+/// Converts the decimal to an `int256` fixed point integer, or returns
+/// `None` if it has more decimal places than allowed or overflows.
+pub(crate) fn to_fixed_point(value: &BigDecimal, decimals: i64) -> Option<I256> {
+    let scaled = value.with_scale(decimals);
+    if &scaled != value {
+        return None;
+    }
+    let (integer, _) = scaled.into_bigint_and_exponent();
+    I256::from_dec_str(&integer.to_string()).ok()
+}
+
+pub(crate) fn encode_uint(value: impl Into<U256>) -> Bytes {
+    value.into().encode().into()
+}
+
+pub(crate) fn encode_address(value: Address) -> Bytes {
+    value.encode().into()
+}
+
+pub(crate) fn decode_decimal(value: &Bytes, decimals: i64) -> Option<BigDecimal> {
+    let integer = I256::from_raw(decode_uint(value)?);
+    let integer = BigInt::from_str(&integer.to_string()).ok()?;
+    Some(BigDecimal::new(integer, decimals).normalized())
+}
+
+pub(crate) fn decode_bool(value: &Bytes) -> Option<bool> {
+    let integer = decode_uint(value)?;
+    if integer > U256::one() {
+        return None;
+    }
+    Some(!integer.is_zero())
+}
+
+pub(crate) fn decode_u64(value: &Bytes) -> Option<u64> {
+    u64::try_from(decode_uint(value)?).ok()
+}
+
+pub(crate) fn decode_address(value: &Bytes) -> Option<Address> {
+    let word = decode_word(value)?;
+    // Addresses are left padded to 32 bytes.
+    if word[..12].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(Address::from_slice(&word[12..]))
+}
+
+fn decode_uint(value: &Bytes) -> Option<U256> {
+    Some(U256::from_big_endian(&decode_word(value)?))
+}
+
+fn decode_word(value: &Bytes) -> Option<[u8; 32]> {
+    value.as_ref().try_into().ok()
+}
@@ -94,6 +94,7 @@ pub struct LpTransactRequest {
 pub struct SetLpParamRequest {
     pub id: LiquidityPoolId,
     // This should be the raw string, it will be utf-8 encoded when sent to the contract.
+    // See [crate::interface::pair::params::LpParam] for the known params.
     pub param_id: String,
     // This is the raw value that will be set in the contract.
     pub value: Bytes,
@@ -113,6 +114,7 @@ pub struct SetLpParamsRequest {
 #[serde(rename_all = "camelCase")]
 pub struct LpParamRequest {
     // This should be the raw string, it will be utf-8 encoded when sent to the contract.
+    // See [crate::interface::pair::params::LpParam] for the known params.
     pub param_id: String,
     // This is the raw value that will be set in the contract.
     pub value: Bytes,
@@ -122,6 +124,7 @@ pub struct LpParamRequest {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SetSystemParamRequest {
+    /// See [crate::interface::system_params] for encoding typed values.
     pub param_id: String,
     pub param_value: Bytes,
     pub admin_request: AdminRequest,
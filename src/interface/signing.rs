@@ -0,0 +1,392 @@
+//! Message hashing and signer recovery for account role signatures.
+//! Messages are ABI encoded, hashed with keccak256 and then signed
+//! as an EIP-191 personal message.
+//! Orders and cancellations are signed as [AccountRole::Trader] role messages
+//! with the off-chain order nonce in place of the user nonce, and with the
+//! ABI encoded order content appended as a payload, so that no order field
+//! can be changed without invalidating the signature.
+//! Pool role messages are signed in the same way, with the scope read from
+//! the liquidity pool contract's `MESSAGE_SCOPE`.
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::order::{LinkedOrderKind, Order, OrderKind};
+use crate::interface::pair::params::LpParamError;
+use crate::interface::param_encoding;
+use crate::interface::requests::{CancelOrderRequest, ReplaceOrderRequest, TradeSize};
+use crate::interface::{AccountId, AccountRole, LiquidityPoolRole};
+use bigdecimal::BigDecimal;
+use ethers::abi;
+use ethers::abi::Token;
+use ethers::contract::Lazy;
+use ethers::prelude::{Address, Bytes, Signature, I256, U256};
+use ethers::utils::{hash_message, keccak256};
+use thiserror::Error;
+use uuid::Uuid;
+
+pub static ACCOUNT_MESSAGE_SCOPE: Lazy<[u8; 32]> =
+    Lazy::new(|| keccak256("HANDLE_SYNTH_ACCOUNT_MESSAGE".as_bytes()));
+
+/// The decimals of the fixed point integers that order sizes and prices
+/// are encoded as in order messages.
+pub const ORDER_DECIMALS: i64 = 18;
+
+#[derive(Debug, Error)]
+pub enum OrderSignatureError {
+    #[error("cannot encode {0} as a fixed point integer")]
+    InvalidDecimal(BigDecimal),
+    #[error("invalid signature: {0}")]
+    InvalidSignature(#[from] ethers::types::SignatureError),
+    #[error("signer {recovered:?} does not match account user {expected:?}")]
+    SignerMismatch {
+        expected: Address,
+        recovered: Address,
+    },
+}
+
+/// Returns the hash that must be signed by an account user with the role,
+/// as validated by the account contract's `validateAccountRoleSignature`.
+pub fn get_role_message_hash(account_id: U256, user_nonce: U256, role: AccountRole) -> [u8; 32] {
+    keccak256(abi::encode(&[
+        Token::FixedBytes(ACCOUNT_MESSAGE_SCOPE.to_vec()),
+        Token::Uint(user_nonce),
+        Token::Uint(account_id),
+        Token::Uint(U256::from(role as u8)),
+    ]))
+}
+
+/// Returns the hash that must be signed by the account user to place the
+/// order, which covers its nonce and [get_order_payload].
+pub fn get_order_message_hash(order: &Order) -> Result<[u8; 32], OrderSignatureError> {
+    let payload = get_order_payload(order)?;
+    Ok(get_trader_message_hash(
+        order.account_id,
+        order.nonce,
+        &payload,
+    ))
+}
+
+/// Returns the hash that must be signed by the account user to cancel the
+/// order, which covers the cancellation nonce and the order ID.
+pub fn get_cancel_order_message_hash(
+    account_id: AccountId,
+    order_id: Uuid,
+    nonce: Uuid,
+) -> [u8; 32] {
+    let payload = abi::encode(&[Token::Uint(U256::from(order_id.as_u128()))]);
+    get_trader_message_hash(account_id, nonce, &payload)
+}
+
+/// Returns the ABI encoding of the order content that is signed:
+/// `(uint256 lpId, string pair, uint8 sizeUnit, int256 size, uint8 kind,
+/// int256 limitPrice, int256 triggerPrice, uint8 linkedOrderKind,
+/// uint256 linkedOrderId, int256 expiryTimestampUnixMillis)`.
+/// Sizes and prices are fixed point integers with [ORDER_DECIMALS], and
+/// prices, linked orders and their IDs that the order kind does not have
+/// are zero. Units and kinds are the indices of the enum variants, and
+/// linked order kinds are one for a position and two for an order.
+pub fn get_order_payload(order: &Order) -> Result<Bytes, OrderSignatureError> {
+    let (size_unit, size) = match &order.size {
+        TradeSize::Lot(size) => (0u8, size),
+        TradeSize::Lpc(size) => (1, size),
+    };
+    let zero = BigDecimal::from(0);
+    let (kind, limit_price, trigger_price, linked_order) = match &order.kind {
+        OrderKind::Market => (0u8, &zero, &zero, None),
+        OrderKind::Limit(args) => (1, &args.limit_price, &zero, None),
+        OrderKind::StopMarket(args) => (2, &zero, &args.trigger_price, args.stop_loss),
+        OrderKind::StopLimit(args) => (3, &args.limit_price, &args.trigger_price, None),
+        OrderKind::LimitTrigger(args) => (4, &zero, &args.trigger_price, args.take_profit),
+    };
+    let (linked_order_kind, linked_order_id) = match linked_order {
+        None => (0u8, 0),
+        Some(LinkedOrderKind::Position) => (1, 0),
+        Some(LinkedOrderKind::Order(id)) => (2, id.as_u128()),
+    };
+    Ok(abi::encode(&[
+        Token::Uint(order.lp_id.as_u256()),
+        Token::String(order.pair.to_string()),
+        Token::Uint(U256::from(size_unit)),
+        encode_decimal(size)?,
+        Token::Uint(U256::from(kind)),
+        encode_decimal(limit_price)?,
+        encode_decimal(trigger_price)?,
+        Token::Uint(U256::from(linked_order_kind)),
+        Token::Uint(U256::from(linked_order_id)),
+        Token::Int(I256::from(order.expiry_timestamp_unix_millis).into_raw()),
+    ])
+    .into())
+}
+
+/// Returns the trader role message hash with the off-chain order nonce,
+/// with the payload appended as for pool role messages.
+fn get_trader_message_hash(account_id: AccountId, nonce: Uuid, payload: &[u8]) -> [u8; 32] {
+    keccak256(abi::encode(&[
+        Token::FixedBytes(ACCOUNT_MESSAGE_SCOPE.to_vec()),
+        Token::Uint(U256::from(nonce.as_u128())),
+        Token::Uint(U256::from(account_id)),
+        Token::Uint(U256::from(AccountRole::Trader as u8)),
+        Token::Bytes(payload.to_vec()),
+    ]))
+}
+
+fn encode_decimal(value: &BigDecimal) -> Result<Token, OrderSignatureError> {
+    let value = param_encoding::to_fixed_point(value, ORDER_DECIMALS)
+        .ok_or_else(|| OrderSignatureError::InvalidDecimal(value.clone()))?;
+    Ok(Token::Int(value.into_raw()))
+}
+
+/// Returns the hash that must be signed by a pool user with the role,
+/// as validated by the liquidity pool contract's `validatePoolRoleSignature`.
+/// The layout mirrors that of account role messages, with the payload
+/// appended. As it is not published, signatures should be checked with
+/// `validatePoolRoleSignature` before use, e.g. as [crate::lp_admin] does.
+pub fn get_pool_role_message_hash(
+    message_scope: &[u8; 32],
+    lp_id: LiquidityPoolId,
+    nonce: U256,
+    role: LiquidityPoolRole,
+    payload: &[u8],
+) -> [u8; 32] {
+    keccak256(abi::encode(&[
+        Token::FixedBytes(message_scope.to_vec()),
+        Token::Uint(nonce),
+        Token::Uint(lp_id.as_u256()),
+        Token::Uint(U256::from(role as u8)),
+        Token::Bytes(payload.to_vec()),
+    ]))
+}
+
+/// Returns the pool role message payload for setting an LP param,
+/// i.e. the `paramId` and `paramValue` of the contract's `SetParamRequest`.
+/// The value must be a single 32 byte word, as it is set as `bytes32`.
+pub fn get_lp_param_payload(param_id: &str, value: &Bytes) -> Result<Bytes, LpParamError> {
+    if value.len() != 32 {
+        return Err(LpParamError::InvalidLength(value.clone()));
+    }
+    Ok(abi::encode(&[
+        Token::String(param_id.to_owned()),
+        Token::FixedBytes(value.to_vec()),
+    ])
+    .into())
+}
+
+/// Returns the pool role message payload for buying (`is_buy`) or selling
+/// `amount` of liquidity tokens in 18 decimals for the recipient.
+/// The liquidity token price is set by the trade server, as for the
+/// contract's `deposit`, so it is not covered.
+/// Depositors hold no pool role, so these messages are signed with
+/// [LiquidityPoolRole::_None]. The layout is this client's, as the
+/// trade server's is not published.
+pub fn get_lp_transact_payload(is_buy: bool, amount: U256, recipient: Address) -> Bytes {
+    abi::encode(&[
+        Token::Bool(is_buy),
+        Token::Uint(amount),
+        Token::Address(recipient),
+    ])
+    .into()
+}
+
+/// Verifies that the pool role message was signed by the pool user.
+pub fn verify_pool_role_signature(
+    message_scope: &[u8; 32],
+    lp_id: LiquidityPoolId,
+    pool_user: Address,
+    nonce: U256,
+    role: LiquidityPoolRole,
+    payload: &[u8],
+    signature: &Bytes,
+) -> Result<(), OrderSignatureError> {
+    let hash = get_pool_role_message_hash(message_scope, lp_id, nonce, role, payload);
+    ensure_signer(pool_user, recover_signer(hash, signature)?)
+}
+
+/// Recovers the address that signed the order.
+pub fn recover_order_signer(order: &Order) -> Result<Address, OrderSignatureError> {
+    recover_signer(get_order_message_hash(order)?, &order.signature)
+}
+
+/// Recovers the address that signed the order cancellation.
+pub fn recover_cancel_order_signer(
+    request: &CancelOrderRequest,
+) -> Result<Address, OrderSignatureError> {
+    let hash = get_cancel_order_message_hash(request.account_id, request.order_id, request.nonce);
+    recover_signer(hash, &request.signature)
+}
+
+/// Recovers the address that signed the cancellation part of an order
+/// replacement. The new order is signed separately.
+pub fn recover_replace_order_signer(
+    request: &ReplaceOrderRequest,
+) -> Result<Address, OrderSignatureError> {
+    let hash = get_cancel_order_message_hash(request.account_id, request.order_id, request.nonce);
+    recover_signer(hash, &request.cancel_signature)
+}
+
+/// Verifies that the order was signed by its account user.
+pub fn verify_order_signature(order: &Order) -> Result<(), OrderSignatureError> {
+    ensure_signer(order.account_user, recover_order_signer(order)?)
+}
+
+/// Verifies that the cancellation was signed by its account user.
+pub fn verify_cancel_order_signature(
+    request: &CancelOrderRequest,
+) -> Result<(), OrderSignatureError> {
+    ensure_signer(request.account_user, recover_cancel_order_signer(request)?)
+}
+
+/// Verifies that both the cancellation and the new order of a replacement
+/// were signed by the account user.
+pub fn verify_replace_order_signature(
+    request: &ReplaceOrderRequest,
+) -> Result<(), OrderSignatureError> {
+    ensure_signer(request.account_user, recover_replace_order_signer(request)?)?;
+    ensure_signer(
+        request.account_user,
+        recover_order_signer(&request.new_order)?,
+    )
+}
+
+fn recover_signer(hash: [u8; 32], signature: &Bytes) -> Result<Address, OrderSignatureError> {
+    let signature = Signature::try_from(signature.as_ref())?;
+    Ok(signature.recover(hash_message(hash))?)
+}
+
+fn ensure_signer(expected: Address, recovered: Address) -> Result<(), OrderSignatureError> {
+    if expected != recovered {
+        return Err(OrderSignatureError::SignerMismatch {
+            expected,
+            recovered,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::order::{LimitOrderArgs, TestOrderBuilder};
+    use crate::interface::pair::Pair;
+    use ethers::abi::AbiEncode;
+    use ethers::prelude::{LocalWallet, Signer};
+    use std::str::FromStr;
+
+    fn get_order(wallet: &LocalWallet) -> Order {
+        let mut order = TestOrderBuilder::default()
+            .lp_id(LiquidityPoolId::new(U256::from(2)))
+            .size(TradeSize::Lot(BigDecimal::from_str("-1.5").unwrap()))
+            .kind(OrderKind::Limit(LimitOrderArgs {
+                limit_price: BigDecimal::from_str("1850.25").unwrap(),
+            }))
+            .account_user(wallet.address())
+            .expiry_timestamp_unix_millis(1_000)
+            .build();
+        let hash = get_order_message_hash(&order).unwrap();
+        let signature: [u8; 65] = wallet.sign_hash(hash_message(hash)).unwrap().into();
+        order.signature = signature.into();
+        order
+    }
+
+    #[test]
+    fn order_signature() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let order = get_order(&wallet);
+        assert_eq!(recover_order_signer(&order).unwrap(), wallet.address());
+        verify_order_signature(&order).unwrap();
+        // Sizes and prices must fit the fixed point encoding.
+        let mut order = get_order(&wallet);
+        order.size = TradeSize::Lot(BigDecimal::from_str("1e-19").unwrap());
+        assert!(matches!(
+            verify_order_signature(&order),
+            Err(OrderSignatureError::InvalidDecimal(_))
+        ));
+    }
+
+    #[test]
+    fn tampered_order_signature() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let tampers: [fn(&mut Order); 9] = [
+            |order| order.nonce = Uuid::new_v4(),
+            |order| order.account_id = 2,
+            |order| order.lp_id = LiquidityPoolId::new(U256::from(3)),
+            |order| order.pair = Pair::new("BTC", "USD").unwrap(),
+            |order| order.size = TradeSize::Lot(BigDecimal::from_str("-15").unwrap()),
+            |order| order.size = TradeSize::Lpc(BigDecimal::from_str("-1.5").unwrap()),
+            |order| {
+                order.kind = OrderKind::Limit(LimitOrderArgs {
+                    limit_price: BigDecimal::from_str("1850.26").unwrap(),
+                })
+            },
+            |order| order.kind = OrderKind::Market,
+            |order| order.expiry_timestamp_unix_millis = 1_001,
+        ];
+        for tamper in tampers {
+            let mut order = get_order(&wallet);
+            tamper(&mut order);
+            assert!(matches!(
+                verify_order_signature(&order),
+                Err(OrderSignatureError::SignerMismatch { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn cancel_order_signature() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let mut request = CancelOrderRequest {
+            account_id: 1,
+            account_user: wallet.address(),
+            order_id: Uuid::now_v7(),
+            nonce: Uuid::new_v4(),
+            signature: Bytes::default(),
+        };
+        let hash =
+            get_cancel_order_message_hash(request.account_id, request.order_id, request.nonce);
+        let signature: [u8; 65] = wallet.sign_hash(hash_message(hash)).unwrap().into();
+        request.signature = signature.into();
+        verify_cancel_order_signature(&request).unwrap();
+        // The signature only cancels the signed order.
+        request.order_id = Uuid::now_v7();
+        assert!(matches!(
+            verify_cancel_order_signature(&request),
+            Err(OrderSignatureError::SignerMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn pool_role_signature() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let lp_id = LiquidityPoolId::new(U256::from(2));
+        let message_scope = keccak256("scope");
+        let value = Bytes::from(U256::from(5).encode());
+        let payload = get_lp_param_payload("maxLeverage", &value).unwrap();
+        let hash = get_pool_role_message_hash(
+            &message_scope,
+            lp_id,
+            U256::one(),
+            LiquidityPoolRole::Owner,
+            &payload,
+        );
+        let signature: Bytes =
+            <[u8; 65]>::from(wallet.sign_hash(hash_message(hash)).unwrap()).into();
+        let verify = |message_scope: &[u8; 32]| {
+            verify_pool_role_signature(
+                message_scope,
+                lp_id,
+                wallet.address(),
+                U256::one(),
+                LiquidityPoolRole::Owner,
+                &payload,
+                &signature,
+            )
+        };
+        verify(&message_scope).unwrap();
+        // The signature is only valid for the contract's scope.
+        assert!(verify(&keccak256("other scope")).is_err());
+        // Values must be a single word, without implicit padding.
+        for length in [31, 33] {
+            assert!(matches!(
+                get_lp_param_payload("maxLeverage", &Bytes::from(vec![0; length])),
+                Err(LpParamError::InvalidLength(_))
+            ));
+        }
+    }
+}
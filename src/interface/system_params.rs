@@ -0,0 +1,333 @@
+//! System params, which protocol admins set for the whole protocol via
+//! [crate::interface::requests::SetSystemParamRequest].
+//! The contracts accept any param ID with a `bytes32` value and do not
+//! define the known IDs, so the value kind of each known param is kept in a
+//! [SystemParamRegistry] JSON file, which should be checked against the
+//! params set on the Beacon contract with [SystemParamRegistry::check].
+//! Params that are not registered can be set from their raw bytes with
+//! [crate::protocol_admin::ProtocolAdminClient::set_system_param_raw].
+//! Values are encoded as a single 32 byte ABI word, in the same way as
+//! LP params, with decimals as fixed point integers with
+//! [SYSTEM_PARAM_DECIMALS].
+use crate::interface::param_encoding;
+use bigdecimal::BigDecimal;
+use ethers::prelude::{Address, Bytes};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const SYSTEM_PARAM_DECIMALS: i64 = 18;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SystemParamKind {
+    Decimal,
+    Bool,
+    Integer,
+    Address,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SystemParamValue {
+    Decimal(BigDecimal),
+    Bool(bool),
+    Integer(u64),
+    Address(Address),
+}
+
+/// A registered system param.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemParamDefinition {
+    pub kind: SystemParamKind,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The known system params, keyed by ID, which determine how their values
+/// are parsed and decoded. It is read from a JSON object of definitions,
+/// e.g. `{"feeFraction": {"kind": "decimal"}}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SystemParamRegistry {
+    params: HashMap<String, SystemParamDefinition>,
+}
+
+/// A param set on the Beacon contract that does not match the registry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "camelCase")]
+pub enum SystemParamMismatch {
+    Unregistered {
+        id: String,
+        value: Bytes,
+    },
+    InvalidValue {
+        id: String,
+        kind: SystemParamKind,
+        value: Bytes,
+    },
+}
+
+/// A system param with a typed value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SystemParam {
+    id: String,
+    value: SystemParamValue,
+}
+
+#[derive(Debug, Error)]
+pub enum SystemParamError {
+    #[error("invalid value {value} for system param {id}")]
+    InvalidValue { id: String, value: String },
+    #[error("unknown system param {0}")]
+    UnknownParam(String),
+    #[error("failed to read system param registry: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid system param registry: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl SystemParamRegistry {
+    pub fn from_json(json: &str) -> Result<Self, SystemParamError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SystemParamError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SystemParamDefinition> {
+        self.params.get(id)
+    }
+
+    /// Adds or replaces a param definition.
+    pub fn insert(&mut self, id: &str, definition: SystemParamDefinition) {
+        self.params.insert(id.to_owned(), definition);
+    }
+
+    /// Returns the registered kind of the param,
+    /// failing with [SystemParamError::UnknownParam] if it is not registered.
+    pub fn kind(&self, id: &str) -> Result<SystemParamKind, SystemParamError> {
+        self.get(id)
+            .map(|definition| definition.kind)
+            .ok_or_else(|| SystemParamError::UnknownParam(id.to_owned()))
+    }
+
+    /// Parses a human-readable value as the param's registered kind.
+    pub fn parse(&self, id: &str, value: &str) -> Result<SystemParam, SystemParamError> {
+        SystemParam::parse(id, self.kind(id)?, value)
+    }
+
+    /// Decodes a value as the param's registered kind.
+    pub fn decode(&self, id: &str, value: &Bytes) -> Result<SystemParam, SystemParamError> {
+        SystemParam::decode(id, self.kind(id)?, value)
+    }
+
+    /// Checks the registry against the params set on the Beacon contract,
+    /// i.e. the `paramId` and `paramValue` of its `SetSystemParam` events,
+    /// returning those that are not registered or not of the registered kind.
+    pub fn check(
+        &self,
+        params: impl IntoIterator<Item = (String, Bytes)>,
+    ) -> Vec<SystemParamMismatch> {
+        params
+            .into_iter()
+            .filter_map(|(id, value)| match self.get(&id) {
+                None => Some(SystemParamMismatch::Unregistered { id, value }),
+                Some(definition) => SystemParam::decode(&id, definition.kind, &value)
+                    .is_err()
+                    .then_some(SystemParamMismatch::InvalidValue {
+                        id,
+                        kind: definition.kind,
+                        value,
+                    }),
+            })
+            .collect()
+    }
+}
+
+impl SystemParam {
+    pub fn new(id: &str, value: SystemParamValue) -> Self {
+        Self {
+            id: id.to_owned(),
+            value,
+        }
+    }
+
+    /// Parses a human-readable value, e.g. `0.001`, `true` or an address,
+    /// as the given kind.
+    pub fn parse(id: &str, kind: SystemParamKind, value: &str) -> Result<Self, SystemParamError> {
+        let invalid_value = || SystemParamError::InvalidValue {
+            id: id.to_owned(),
+            value: value.to_owned(),
+        };
+        let value = match kind {
+            SystemParamKind::Decimal => {
+                SystemParamValue::Decimal(BigDecimal::from_str(value).map_err(|_| invalid_value())?)
+            }
+            SystemParamKind::Bool => {
+                SystemParamValue::Bool(bool::from_str(value).map_err(|_| invalid_value())?)
+            }
+            SystemParamKind::Integer => {
+                SystemParamValue::Integer(u64::from_str(value).map_err(|_| invalid_value())?)
+            }
+            SystemParamKind::Address => {
+                SystemParamValue::Address(Address::from_str(value).map_err(|_| invalid_value())?)
+            }
+        };
+        Ok(Self::new(id, value))
+    }
+
+    /// Decodes a param from its ID and encoded value as the given kind.
+    pub fn decode(
+        id: &str,
+        kind: SystemParamKind,
+        value: &Bytes,
+    ) -> Result<Self, SystemParamError> {
+        let decoded = match kind {
+            SystemParamKind::Decimal => {
+                param_encoding::decode_decimal(value, SYSTEM_PARAM_DECIMALS)
+                    .map(SystemParamValue::Decimal)
+            }
+            SystemParamKind::Bool => param_encoding::decode_bool(value).map(SystemParamValue::Bool),
+            SystemParamKind::Integer => {
+                param_encoding::decode_u64(value).map(SystemParamValue::Integer)
+            }
+            SystemParamKind::Address => {
+                param_encoding::decode_address(value).map(SystemParamValue::Address)
+            }
+        };
+        let value = decoded.ok_or_else(|| SystemParamError::InvalidValue {
+            id: id.to_owned(),
+            value: value.to_string(),
+        })?;
+        Ok(Self::new(id, value))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn value(&self) -> &SystemParamValue {
+        &self.value
+    }
+
+    /// Encodes the value as a 32 byte ABI word.
+    pub fn encode(&self) -> Result<Bytes, SystemParamError> {
+        Ok(match &self.value {
+            SystemParamValue::Decimal(value) => {
+                param_encoding::encode_decimal(value, SYSTEM_PARAM_DECIMALS).ok_or_else(|| {
+                    SystemParamError::InvalidValue {
+                        id: self.id.clone(),
+                        value: value.to_string(),
+                    }
+                })?
+            }
+            SystemParamValue::Bool(value) => param_encoding::encode_uint(*value as u8),
+            SystemParamValue::Integer(value) => param_encoding::encode_uint(*value),
+            SystemParamValue::Address(value) => param_encoding::encode_address(*value),
+        })
+    }
+}
+
+impl SystemParamValue {
+    pub fn kind(&self) -> SystemParamKind {
+        match self {
+            Self::Decimal(_) => SystemParamKind::Decimal,
+            Self::Bool(_) => SystemParamKind::Bool,
+            Self::Integer(_) => SystemParamKind::Integer,
+            Self::Address(_) => SystemParamKind::Address,
+        }
+    }
+}
+
+impl Display for SystemParamValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decimal(value) => write!(f, "{value}"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Integer(value) => write!(f, "{value}"),
+            Self::Address(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_encode_and_decode() {
+        let params = [
+            SystemParam::parse("feeFraction", SystemParamKind::Decimal, "0.0005").unwrap(),
+            SystemParam::parse("isEnabled", SystemParamKind::Bool, "true").unwrap(),
+            SystemParam::parse("maxOrders", SystemParamKind::Integer, "100").unwrap(),
+            SystemParam::parse(
+                "recipient",
+                SystemParamKind::Address,
+                &format!("{:?}", Address::random()),
+            )
+            .unwrap(),
+        ];
+        for param in params {
+            let value = param.encode().unwrap();
+            assert_eq!(value.len(), 32);
+            let kind = param.value().kind();
+            assert_eq!(
+                SystemParam::decode(param.id(), kind, &value).unwrap(),
+                param
+            );
+        }
+        assert!(matches!(
+            SystemParam::parse("isEnabled", SystemParamKind::Bool, "1"),
+            Err(SystemParamError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn registry() {
+        let registry = SystemParamRegistry::from_json(
+            r#"{
+                "feeFraction": {"kind": "decimal", "description": "The fee."},
+                "isEnabled": {"kind": "bool"}
+            }"#,
+        )
+        .unwrap();
+        // Values should be parsed as their registered kind;
+        assert_eq!(
+            registry.parse("feeFraction", "0.5").unwrap().value(),
+            &SystemParamValue::Decimal(BigDecimal::from_str("0.5").unwrap())
+        );
+        assert!(registry.parse("isEnabled", "0.5").is_err());
+        assert!(matches!(
+            registry.parse("maxOrders", "100"),
+            Err(SystemParamError::UnknownParam(_))
+        ));
+        // And params set on chain should be checked against the registry.
+        let enabled = SystemParam::parse("isEnabled", SystemParamKind::Bool, "true").unwrap();
+        let set_params = [
+            ("isEnabled".to_owned(), enabled.encode().unwrap()),
+            ("feeFraction".to_owned(), enabled.encode().unwrap()),
+            ("isEnabled".to_owned(), param_encoding::encode_uint(2)),
+            ("maxOrders".to_owned(), param_encoding::encode_uint(100)),
+        ];
+        assert_eq!(
+            registry.check(set_params),
+            vec![
+                SystemParamMismatch::InvalidValue {
+                    id: "isEnabled".to_owned(),
+                    kind: SystemParamKind::Bool,
+                    value: param_encoding::encode_uint(2),
+                },
+                SystemParamMismatch::Unregistered {
+                    id: "maxOrders".to_owned(),
+                    value: param_encoding::encode_uint(100),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Key management: encrypted JSON keystores, wallet generation and
+//! trader sub-keys derived from a mnemonic.
+use ethers::prelude::{LocalWallet, MnemonicBuilder, WalletError};
+use ethers::signers::coins_bip39::{English, Mnemonic};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+use thiserror::Error;
+
+/// The BIP-44 derivation path prefix for Ethereum accounts.
+pub const DERIVATION_PATH_PREFIX: &str = "m/44'/60'/0'/0/";
+
+/// Where to read a keystore password from.
+#[derive(Debug, Clone)]
+pub enum KeystorePassword {
+    /// Prompts for the password on the terminal without echoing it.
+    Prompt,
+    /// Reads the password from the first line of a file.
+    File(PathBuf),
+    /// Reads the password from an environment variable.
+    Env(String),
+    Value(String),
+}
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("wallet error: {0}")]
+    Wallet(#[from] WalletError),
+    #[error("failed to read password: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("missing environment variable {0}")]
+    MissingEnv(String),
+    #[error("invalid mnemonic: {0}")]
+    Mnemonic(String),
+}
+
+impl KeystorePassword {
+    pub fn read(&self) -> Result<String, KeyError> {
+        match self {
+            KeystorePassword::Prompt => Ok(rpassword::prompt_password("Keystore password: ")?),
+            KeystorePassword::File(path) => {
+                let contents = fs::read_to_string(path)?;
+                Ok(contents.lines().next().unwrap_or_default().to_owned())
+            }
+            KeystorePassword::Env(name) => {
+                env::var(name).map_err(|_| KeyError::MissingEnv(name.clone()))
+            }
+            KeystorePassword::Value(password) => Ok(password.clone()),
+        }
+    }
+}
+
+pub fn decrypt_keystore(
+    path: impl AsRef<Path>,
+    password: &KeystorePassword,
+) -> Result<LocalWallet, KeyError> {
+    Ok(LocalWallet::decrypt_keystore(path, password.read()?)?)
+}
+
+/// Generates a new wallet and stores it as an encrypted keystore in `dir`.
+/// Returns the wallet and the keystore file name.
+pub fn generate_keystore(
+    dir: impl AsRef<Path>,
+    password: &KeystorePassword,
+    name: Option<&str>,
+) -> Result<(LocalWallet, String), KeyError> {
+    let password = password.read()?;
+    Ok(LocalWallet::new_keystore(
+        dir,
+        &mut rand::thread_rng(),
+        password,
+        name,
+    )?)
+}
+
+pub fn generate_wallet() -> LocalWallet {
+    LocalWallet::new(&mut rand::thread_rng())
+}
+
+/// Generates a new English mnemonic phrase with the given word count,
+/// e.g. 12 or 24.
+pub fn generate_mnemonic(word_count: usize) -> Result<String, KeyError> {
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), word_count)
+        .map_err(|e| KeyError::Mnemonic(e.to_string()))?;
+    Ok(mnemonic.to_phrase())
+}
+
+/// Derives the wallet at the index of the standard Ethereum derivation path.
+pub fn derive_wallet(phrase: &str, index: u32) -> Result<LocalWallet, KeyError> {
+    Ok(MnemonicBuilder::<English>::default()
+        .phrase(phrase)
+        .index(index)?
+        .build()?)
+}
+
+/// Derives `count` consecutive wallets starting at index `start`,
+/// e.g. for trader sub-keys to be granted [crate::interface::AccountRole::Trader].
+pub fn derive_wallets(phrase: &str, start: u32, count: u32) -> Result<Vec<LocalWallet>, KeyError> {
+    (start..start + count)
+        .map(|index| derive_wallet(phrase, index))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::prelude::Signer;
+
+    #[test]
+    fn keystore_and_mnemonic() {
+        let dir = env::temp_dir().join(format!("handle-synths-keys-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let password = KeystorePassword::Value("password".to_owned());
+        // Given a generated keystore;
+        let (wallet, name) = generate_keystore(&dir, &password, None).unwrap();
+        // Then it should decrypt to the same wallet;
+        let decrypted = decrypt_keystore(dir.join(name), &password).unwrap();
+        assert_eq!(decrypted.address(), wallet.address());
+        fs::remove_dir_all(&dir).unwrap();
+        // Given a mnemonic;
+        let phrase = generate_mnemonic(12).unwrap();
+        // Then derived wallets should be distinct and deterministic;
+        let wallets = derive_wallets(&phrase, 0, 3).unwrap();
+        assert_eq!(wallets.len(), 3);
+        assert_ne!(wallets[0].address(), wallets[1].address());
+        assert_eq!(
+            wallets[2].address(),
+            derive_wallet(&phrase, 2).unwrap().address()
+        );
+    }
+}
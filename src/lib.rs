@@ -2,12 +2,29 @@
 pub mod client_connection;
 #[cfg(not(feature = "interface-only"))]
 pub mod environment;
+#[cfg(not(feature = "interface-only"))]
+pub mod error;
 pub mod interface;
 #[cfg(not(feature = "interface-only"))]
+pub mod keys;
+#[cfg(not(feature = "interface-only"))]
 pub mod liquidity_pool;
 #[cfg(not(feature = "interface-only"))]
+pub mod lp_admin;
+#[cfg(not(feature = "interface-only"))]
+pub mod lp_config;
+#[cfg(all(any(test, feature = "test-utils"), not(feature = "interface-only")))]
+pub mod mock_chain;
+#[cfg(all(any(test, feature = "test-utils"), not(feature = "interface-only")))]
+pub mod mock_server;
+#[cfg(not(feature = "interface-only"))]
+pub mod protocol_admin;
+pub mod risk;
+#[cfg(not(feature = "interface-only"))]
 pub mod trade_account;
 #[cfg(not(feature = "interface-only"))]
+pub mod trade_account_state;
+#[cfg(not(feature = "interface-only"))]
 pub mod user;
 #[cfg(not(feature = "interface-only"))]
 pub mod utils;
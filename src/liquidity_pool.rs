@@ -1,9 +1,343 @@
+use crate::client_connection::{ClientConnection, Subscription};
+use crate::error::ClientError;
+use crate::interface::contract_types::{PoolData, IERC20};
+use crate::interface::events::{Event, LpTokenBuyEvent, LpTokenSellEvent};
 use crate::interface::liquidity_pool::LiquidityPoolId;
-use crate::interface::{Request, RequestContent, SubscriptionTopic};
+use crate::interface::requests::LpTransactRequest;
+use crate::interface::signing::get_lp_transact_payload;
+use crate::interface::{
+    LiquidityPoolRole, Request, RequestContent, ResponseContent, SubscriptionTopic,
+};
+use crate::user::User;
+use crate::utils::{ensure_token_approval, get_deposit_token_amount};
+use bigdecimal::BigDecimal;
+use ethers::prelude::{Address, Http, LocalWallet, Middleware, Provider, Signer, U256};
 
-pub fn get_subscribe_trades_message(lp_id_hex: &str) -> String {
-    let lp_id = LiquidityPoolId::from_hex_str(lp_id_hex).unwrap();
+/// A client for buying and selling the liquidity tokens of a pool.
+/// Transactions are signed by the user as pool role messages for the
+/// user's liquidity pool nonce, with [get_lp_transact_payload].
+#[derive(Clone)]
+pub struct LiquidityPoolClient<S = LocalWallet, M = Provider<Http>> {
+    pub id: LiquidityPoolId,
+    pub user: User<S, M>,
+    pub connection: ClientConnection,
+}
+
+impl<S: Signer + Clone + 'static, M: Middleware + 'static> LiquidityPoolClient<S, M> {
+    pub fn new(id: LiquidityPoolId, user: User<S, M>, connection: ClientConnection) -> Self {
+        Self {
+            id,
+            user,
+            connection,
+        }
+    }
+
+    /// Returns the pool data stored on chain.
+    pub async fn get_pool_data(&self) -> Result<PoolData, ClientError> {
+        let call = self
+            .user
+            .contracts
+            .liquidity_pool
+            .get_pool_data(self.id.as_u256());
+        Ok(call.call().await?)
+    }
+
+    /// Returns the user's liquidity pool nonce, e.g. for signing transactions.
+    pub async fn get_nonce(&self) -> Result<U256, ClientError> {
+        self.user.get_lp_nonce().await
+    }
+
+    /// Returns the number of pools owned by the address.
+    pub async fn get_owned_pool_count(&self, owner: Address) -> Result<U256, ClientError> {
+        let call = self.user.contracts.liquidity_pool.balance_of(owner);
+        Ok(call.call().await?)
+    }
+
+    /// Returns the pool's liquidity token balance of the address.
+    pub async fn get_liquidity_token_balance(&self, holder: Address) -> Result<U256, ClientError> {
+        let pool_data = self.get_pool_data().await?;
+        let token = IERC20::new(
+            pool_data.liquidity_token,
+            self.user.contracts.liquidity_pool.client(),
+        );
+        Ok(token.balance_of(holder).call().await?)
+    }
+
+    /// Buys liquidity tokens by paying `amount` of the underlying token,
+    /// approving the liquidity pool contract to spend it if needed.
+    pub async fn buy_lp_token(&self, amount: BigDecimal) -> Result<LpTokenBuyEvent, ClientError> {
+        let pool_data = self.get_open_pool_data().await?;
+        ensure_token_approval(
+            &self.user.contracts,
+            &self.user.signer,
+            get_deposit_token_amount(&amount)?,
+            pool_data.underlying_token,
+            self.user.contracts.liquidity_pool.address(),
+        )
+        .await?;
+        let request = self.get_transact_request(true, amount).await?;
+        let response = self
+            .connection
+            .send_request(RequestContent::BuyLpToken(request))
+            .await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::BuyLpToken(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "buy LP token event",
+                content,
+            )),
+        }
+    }
+
+    /// Sells `amount` of liquidity tokens for the underlying token.
+    pub async fn sell_lp_token(&self, amount: BigDecimal) -> Result<LpTokenSellEvent, ClientError> {
+        self.get_open_pool_data().await?;
+        let request = self.get_transact_request(false, amount).await?;
+        let response = self
+            .connection
+            .send_request(RequestContent::SellLpToken(request))
+            .await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::SellLpToken(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "sell LP token event",
+                content,
+            )),
+        }
+    }
+
+    /// Subscribes to all trades in the pool.
+    pub async fn subscribe_trades(&self) -> Result<Subscription, ClientError> {
+        subscribe_trades(&self.connection, self.id).await
+    }
+
+    async fn get_open_pool_data(&self) -> Result<PoolData, ClientError> {
+        let pool_data = self.get_pool_data().await?;
+        if !pool_data.is_open {
+            return Err(ClientError::InvalidArgument(format!(
+                "liquidity pool {} is not open",
+                self.id
+            )));
+        }
+        Ok(pool_data)
+    }
+
+    async fn get_transact_request(
+        &self,
+        is_buy: bool,
+        amount: BigDecimal,
+    ) -> Result<LpTransactRequest, ClientError> {
+        let message_scope = self.user.get_lp_message_scope().await?;
+        let nonce = self.get_nonce().await?;
+        let payload = get_lp_transact_payload(
+            is_buy,
+            get_deposit_token_amount(&amount)?,
+            self.user.address,
+        );
+        let signature: [u8; 65] = self
+            .user
+            .sign_pool_role_message(
+                &message_scope,
+                self.id,
+                nonce,
+                LiquidityPoolRole::_None,
+                &payload,
+            )
+            .await?
+            .into();
+        Ok(LpTransactRequest {
+            lp_id: self.id,
+            amount,
+            address: self.user.address,
+            signature: signature.into(),
+        })
+    }
+}
+
+pub fn get_subscribe_trades_message(lp_id_hex: &str) -> Result<String, ClientError> {
+    let lp_id = LiquidityPoolId::from_hex_str(lp_id_hex)
+        .map_err(|e| ClientError::InvalidArgument(format!("invalid LP ID {lp_id_hex}: {e}")))?;
     let topic = SubscriptionTopic::LiquidityPoolTrade(lp_id);
     let request = Request::from(RequestContent::Subscribe(topic), None);
-    serde_json::to_string(&request).unwrap()
+    Ok(serde_json::to_string(&request)?)
+}
+
+/// Subscribes to all trades in the LP, which are published as
+/// [crate::interface::Publication::LpTrade].
+pub async fn subscribe_trades(
+    connection: &ClientConnection,
+    lp_id: LiquidityPoolId,
+) -> Result<Subscription, ClientError> {
+    connection
+        .subscribe(SubscriptionTopic::LiquidityPoolTrade(lp_id))
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::environment::{get_network_config, Contracts};
+    use crate::interface::signing::verify_pool_role_signature;
+    use crate::mock_chain::MockChain;
+    use crate::mock_server::MockServer;
+    use ethers::abi::{self, Tokenizable};
+    use ethers::prelude::{Bytes, MockProvider, SignerMiddleware};
+    use ethers::utils::keccak256;
+    use std::sync::Arc;
+
+    /// Returns a user that signs locally, with chain calls answered by the
+    /// returned mock provider in the reverse order they are pushed.
+    fn get_mock_user() -> (User<LocalWallet, Provider<MockProvider>>, MockProvider) {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let (provider, mock) = Provider::mocked();
+        let client = Arc::new(SignerMiddleware::new(provider, wallet.clone()));
+        let network = get_network_config("arbitrum-sepolia").unwrap();
+        let user = User {
+            address: wallet.address(),
+            signer: wallet,
+            contracts: Contracts::from_network_config(&network, client).unwrap(),
+        };
+        (user, mock)
+    }
+
+    #[tokio::test]
+    async fn sell_lp_token_offline() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let (user, mock) = get_mock_user();
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        let client = LiquidityPoolClient::new(lp_id, user, connection);
+        server.set_handler(|request| match request {
+            RequestContent::SellLpToken(request) => Some(Ok(ResponseContent::Event(
+                Event::SellLpToken(LpTokenSellEvent {
+                    address: request.address,
+                    lp_id: request.lp_id,
+                    pay_amount: request.amount.clone(),
+                    buy_amount: BigDecimal::from(99),
+                    timestamp_unix_millis: 1,
+                    signature: request.signature.clone(),
+                }),
+            ))),
+            _ => None,
+        });
+        // Given an open pool, the contract's message scope and the user's nonce;
+        let message_scope = keccak256("scope");
+        let nonce = U256::from(3);
+        let pool_data = PoolData {
+            is_open: true,
+            underlying_token: Address::random(),
+            liquidity_token: Address::random(),
+            owner: Address::random(),
+        };
+        let push_result = |token| {
+            mock.push::<Bytes, _>(Bytes::from(abi::encode(&[token])))
+                .unwrap()
+        };
+        push_result(nonce.into_token());
+        push_result(message_scope.into_token());
+        push_result(pool_data.clone().into_token());
+        // When selling liquidity tokens;
+        let event = client.sell_lp_token(BigDecimal::from(100)).await.unwrap();
+        // Then the server should have received the request,
+        // signed by the user for the nonce;
+        let requests = server.requests();
+        let Some(RequestContent::SellLpToken(received)) = requests.last() else {
+            panic!("did not receive sell LP token request; {requests:#?}");
+        };
+        assert_eq!(received.lp_id, lp_id);
+        assert_eq!(received.amount, BigDecimal::from(100));
+        assert_eq!(received.address, client.user.address);
+        let verify = |is_buy: bool, nonce: U256| {
+            let payload = get_lp_transact_payload(is_buy, U256::exp10(20), client.user.address);
+            verify_pool_role_signature(
+                &message_scope,
+                lp_id,
+                client.user.address,
+                nonce,
+                LiquidityPoolRole::_None,
+                &payload,
+                &received.signature,
+            )
+        };
+        verify(false, nonce).unwrap();
+        assert!(verify(true, nonce).is_err());
+        assert!(verify(false, nonce + 1).is_err());
+        // And the sell event should be returned.
+        assert_eq!(event.address, client.user.address);
+        assert_eq!(event.lp_id, lp_id);
+        assert_eq!(event.buy_amount, BigDecimal::from(99));
+        assert_eq!(event.signature, received.signature);
+        // When the pool is closed;
+        let pool_data = PoolData {
+            is_open: false,
+            ..pool_data
+        };
+        push_result(pool_data.into_token());
+        // Then selling should fail without sending a request.
+        assert!(client.sell_lp_token(BigDecimal::from(100)).await.is_err());
+        assert_eq!(server.requests().len(), requests.len());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn buy_lp_token() {
+        let chain = MockChain::spawn().await.unwrap();
+        let server = MockServer::start().await.unwrap();
+        let user = chain.user(1).await.unwrap();
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        chain
+            .open_pool(lp_id.as_u256(), Address::random(), user.address)
+            .await
+            .unwrap();
+        server.set_handler(|request| match request {
+            RequestContent::BuyLpToken(request) => Some(Ok(ResponseContent::Event(
+                Event::BuyLpToken(LpTokenBuyEvent {
+                    address: request.address,
+                    lp_id: request.lp_id,
+                    pay_amount: request.amount.clone(),
+                    buy_amount: request.amount.clone(),
+                    timestamp_unix_millis: 0,
+                    signature: request.signature.clone(),
+                }),
+            ))),
+            _ => None,
+        });
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let client = LiquidityPoolClient::new(lp_id, user, connection);
+        assert_eq!(
+            client
+                .get_owned_pool_count(client.user.address)
+                .await
+                .unwrap(),
+            U256::one()
+        );
+        // When buying liquidity tokens;
+        let nonce = client.get_nonce().await.unwrap();
+        let event = client.buy_lp_token(BigDecimal::from(100)).await.unwrap();
+        // Then the server should have received the signed request,
+        // and the pool should have been approved to spend the payment.
+        assert_eq!(event.pay_amount, BigDecimal::from(100));
+        verify_pool_role_signature(
+            &client.user.get_lp_message_scope().await.unwrap(),
+            lp_id,
+            client.user.address,
+            nonce,
+            LiquidityPoolRole::_None,
+            &get_lp_transact_payload(true, U256::exp10(20), client.user.address),
+            &event.signature,
+        )
+        .unwrap();
+        let token = IERC20::new(chain.network().usd, client.user.contracts.account.client());
+        let allowance = token
+            .allowance(
+                client.user.address,
+                client.user.contracts.liquidity_pool.address(),
+            )
+            .call()
+            .await
+            .unwrap();
+        assert_eq!(allowance, U256::exp10(20));
+    }
 }
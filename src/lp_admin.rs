@@ -0,0 +1,308 @@
+use crate::client_connection::ClientConnection;
+use crate::error::ClientError;
+use crate::interface::events::{Event, SetLpParamEvent};
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::pair::params::{LpParam, LpParamIdFormat};
+use crate::interface::pair::Pair;
+use crate::interface::requests::{LpParamRequest, SetLpParamRequest, SetLpParamsRequest};
+use crate::interface::signing::{get_lp_param_payload, verify_pool_role_signature};
+use crate::interface::{LiquidityPoolRole, RequestContent, ResponseContent};
+use crate::user::User;
+use ethers::prelude::{Bytes, Http, LocalWallet, Middleware, Provider, Signer, U256};
+
+/// A client for the owner of a liquidity pool to set its params.
+/// Requests are signed with the [LiquidityPoolRole::Owner] pool role,
+/// and checked with the liquidity pool contract before being sent.
+/// Typed pair params are keyed with `param_id_format`, which should be set
+/// to the trade server's format if it differs from the default guess.
+#[derive(Clone)]
+pub struct LpAdminClient<S = LocalWallet, M = Provider<Http>> {
+    pub id: LiquidityPoolId,
+    pub user: User<S, M>,
+    pub connection: ClientConnection,
+    pub param_id_format: LpParamIdFormat,
+}
+
+impl<S: Signer + Clone + 'static, M: Middleware + 'static> LpAdminClient<S, M> {
+    pub fn new(id: LiquidityPoolId, user: User<S, M>, connection: ClientConnection) -> Self {
+        Self {
+            id,
+            user,
+            connection,
+            param_id_format: LpParamIdFormat::default(),
+        }
+    }
+
+    pub fn with_param_id_format(mut self, format: LpParamIdFormat) -> Self {
+        self.param_id_format = format;
+        self
+    }
+
+    /// Sets a typed param for the pair.
+    pub async fn set_pair_param(
+        &self,
+        pair: &Pair,
+        param: LpParam,
+    ) -> Result<SetLpParamEvent, ClientError> {
+        self.set_lp_param(
+            &param.param_id(pair, &self.param_id_format),
+            param.encode()?,
+        )
+        .await
+    }
+
+    /// Sets multiple typed params for the pair in one request.
+    pub async fn set_pair_params(
+        &self,
+        pair: &Pair,
+        params: Vec<LpParam>,
+    ) -> Result<Vec<SetLpParamEvent>, ClientError> {
+        let params = params
+            .into_iter()
+            .map(|param| {
+                let param_id = param.param_id(pair, &self.param_id_format);
+                Ok((param_id, param.encode()?))
+            })
+            .collect::<Result<_, ClientError>>()?;
+        self.set_lp_params(params).await
+    }
+
+    /// Sets a single param from its raw ID and encoded value,
+    /// returning the event with the param's effect.
+    /// Prefer [Self::set_pair_param] for known params.
+    pub async fn set_lp_param(
+        &self,
+        param_id: &str,
+        value: Bytes,
+    ) -> Result<SetLpParamEvent, ClientError> {
+        let message_scope = self.user.get_lp_message_scope().await?;
+        let nonce = self.user.get_lp_nonce().await?;
+        let payload = get_lp_param_payload(param_id, &value)?;
+        let signature = self.sign_payload(&message_scope, &payload, nonce).await?;
+        self.validate_signature(payload, signature.clone()).await?;
+        let request = RequestContent::SetLpParam(SetLpParamRequest {
+            id: self.id,
+            param_id: param_id.to_owned(),
+            value,
+            pool_user: self.user.address,
+            signature,
+        });
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::SetLpParam(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "set LP param event",
+                content,
+            )),
+        }
+    }
+
+    /// Sets multiple params in one request, returning an event per param.
+    /// The contract consumes one nonce per param, so the params are
+    /// signed with consecutive nonces in the given order.
+    /// Only the first signature can be checked with the contract before
+    /// sending, as the others are for nonces that are not yet current.
+    /// Each is checked locally against the user for its nonce instead, as
+    /// the first being valid on chain confirms the shared message layout.
+    pub async fn set_lp_params(
+        &self,
+        params: Vec<(String, Bytes)>,
+    ) -> Result<Vec<SetLpParamEvent>, ClientError> {
+        let message_scope = self.user.get_lp_message_scope().await?;
+        let nonce = self.user.get_lp_nonce().await?;
+        let mut param_requests = Vec::with_capacity(params.len());
+        for (i, (param_id, value)) in params.into_iter().enumerate() {
+            let payload = get_lp_param_payload(&param_id, &value)?;
+            let signature = self
+                .sign_payload(&message_scope, &payload, nonce + i)
+                .await?;
+            verify_pool_role_signature(
+                &message_scope,
+                self.id,
+                self.user.address,
+                nonce + i,
+                LiquidityPoolRole::Owner,
+                &payload,
+                &signature,
+            )?;
+            if i == 0 {
+                self.validate_signature(payload, signature.clone()).await?;
+            }
+            param_requests.push(LpParamRequest {
+                param_id,
+                value,
+                signature,
+            });
+        }
+        let request = RequestContent::SetLpParams(SetLpParamsRequest {
+            id: self.id,
+            pool_user: self.user.address,
+            params: param_requests,
+        });
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::SetLpParams(events)) => Ok(events),
+            content => Err(ClientError::unexpected_response(
+                "set LP params event",
+                content,
+            )),
+        }
+    }
+
+    async fn sign_payload(
+        &self,
+        message_scope: &[u8; 32],
+        payload: &[u8],
+        nonce: U256,
+    ) -> Result<Bytes, ClientError> {
+        let signature: [u8; 65] = self
+            .user
+            .sign_pool_role_message(
+                message_scope,
+                self.id,
+                nonce,
+                LiquidityPoolRole::Owner,
+                payload,
+            )
+            .await?
+            .into();
+        Ok(signature.into())
+    }
+
+    async fn validate_signature(
+        &self,
+        payload: Bytes,
+        signature: Bytes,
+    ) -> Result<(), ClientError> {
+        self.user
+            .validate_pool_role_signature(self.id, LiquidityPoolRole::Owner, payload, signature)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::events::LpParamEffect;
+    use crate::mock_chain::MockChain;
+    use crate::mock_server::MockServer;
+    use ethers::abi::AbiEncode;
+    use ethers::prelude::Address;
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn set_lp_param() {
+        let chain = MockChain::spawn().await.unwrap();
+        let server = MockServer::start().await.unwrap();
+        let user = chain.user(1).await.unwrap();
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        chain
+            .open_pool(lp_id.as_u256(), Address::random(), user.address)
+            .await
+            .unwrap();
+        server.set_handler(|request| match request {
+            RequestContent::SetLpParam(request) => Some(Ok(ResponseContent::Event(
+                Event::SetLpParam(SetLpParamEvent {
+                    id: request.id,
+                    param_id: request.param_id.clone(),
+                    value: request.value.clone(),
+                    pool_user: request.pool_user,
+                    signature: request.signature.clone(),
+                    effect: LpParamEffect::None,
+                }),
+            ))),
+            _ => None,
+        });
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let client = LpAdminClient::new(lp_id, user, connection);
+        // When setting a param;
+        let value = Bytes::from(U256::from(50).encode());
+        let event = client
+            .set_lp_param("maxLeverage", value.clone())
+            .await
+            .unwrap();
+        assert!(matches!(event.effect, LpParamEffect::None));
+        // Then the signature should be valid for the contract.
+        let payload = get_lp_param_payload("maxLeverage", &value).unwrap();
+        client
+            .user
+            .contracts
+            .liquidity_pool
+            .validate_pool_role_signature(
+                lp_id.as_u256(),
+                client.user.address,
+                LiquidityPoolRole::Owner as u8,
+                payload,
+                event.signature,
+            )
+            .call()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn set_lp_params() {
+        let chain = MockChain::spawn().await.unwrap();
+        let server = MockServer::start().await.unwrap();
+        let user = chain.user(1).await.unwrap();
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        chain
+            .open_pool(lp_id.as_u256(), Address::random(), user.address)
+            .await
+            .unwrap();
+        server.set_handler(|request| match request {
+            RequestContent::SetLpParams(request) => {
+                let events = request
+                    .params
+                    .iter()
+                    .map(|param| SetLpParamEvent {
+                        id: request.id,
+                        param_id: param.param_id.clone(),
+                        value: param.value.clone(),
+                        pool_user: request.pool_user,
+                        signature: param.signature.clone(),
+                        effect: LpParamEffect::None,
+                    })
+                    .collect();
+                Some(Ok(ResponseContent::Event(Event::SetLpParams(events))))
+            }
+            _ => None,
+        });
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let client = LpAdminClient::new(lp_id, user, connection);
+        // When setting multiple params;
+        let params: Vec<_> = ["maxLeverage", "minLeverage", "fee"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| (id.to_owned(), Bytes::from(U256::from(i).encode())))
+            .collect();
+        let events = client.set_lp_params(params.clone()).await.unwrap();
+        assert_eq!(events.len(), params.len());
+        // Then every signature should be valid for the contract
+        // when consumed in order.
+        let liquidity_pool = &client.user.contracts.liquidity_pool;
+        for ((param_id, value), event) in params.iter().zip(events) {
+            let payload = get_lp_param_payload(param_id, value).unwrap();
+            liquidity_pool
+                .consume_pool_role_signature(
+                    lp_id.as_u256(),
+                    client.user.address,
+                    LiquidityPoolRole::Owner as u8,
+                    payload,
+                    event.signature,
+                )
+                .send()
+                .await
+                .unwrap()
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            client.user.get_lp_nonce().await.unwrap(),
+            U256::from(params.len())
+        );
+    }
+}
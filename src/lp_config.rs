@@ -0,0 +1,224 @@
+use crate::client_connection::ClientConnection;
+use crate::error::ClientError;
+use crate::interface::events::{Event, LpConfigUpdateEvent};
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::pair::config::{LpConfig, PairConfig};
+use crate::interface::{LpPair, RequestContent, ResponseContent};
+use tokio::sync::watch;
+
+/// Requests the config of all LP pairs, which the server responds to with
+/// an [Event::LpConfigUpdate] of every pair as a snapshot.
+pub async fn get_lp_config(connection: &ClientConnection) -> Result<LpConfig, ClientError> {
+    let response = connection.send_request(RequestContent::GetLpConfig).await?;
+    let content = response.content()?;
+    match content {
+        ResponseContent::Event(Event::LpConfigUpdate(updates)) => Ok(LpConfig::from(updates)),
+        content => Err(ClientError::unexpected_response(
+            "LP config update event",
+            content,
+        )),
+    }
+}
+
+/// A local copy of LP pair configs, kept up to date from
+/// [Event::LpConfigUpdate] events, e.g. as returned when setting LP params.
+#[derive(Debug, Clone, Default)]
+pub struct LpConfigCache {
+    config: LpConfig,
+    /// The LPs whose pairs are cached, or `None` for all LPs.
+    lp_ids: Option<Vec<LiquidityPoolId>>,
+}
+
+/// Shares LP pair configs of the given LPs between tasks, which are
+/// notified whenever an applied event changes a config.
+/// No topic publishes config updates, so the mirror is only kept in sync
+/// by the events and responses passed to [LpConfigMirror::apply_event] and
+/// [LpConfigMirror::apply_response], and by [LpConfigMirror::refresh].
+#[derive(Debug)]
+pub struct LpConfigMirror {
+    tx: watch::Sender<LpConfigCache>,
+    rx: watch::Receiver<LpConfigCache>,
+}
+
+impl LpConfigCache {
+    pub fn new(config: LpConfig) -> Self {
+        Self {
+            config,
+            lp_ids: None,
+        }
+    }
+
+    /// Caches only the pairs of the given LPs, ignoring all others.
+    pub fn for_lps(config: LpConfig, lp_ids: &[LiquidityPoolId]) -> Self {
+        let config = config
+            .into_inner()
+            .into_iter()
+            .filter(|(lp_pair, _)| lp_ids.contains(&lp_pair.lp_id))
+            .collect();
+        Self {
+            config: LpConfig::new(config),
+            lp_ids: Some(lp_ids.to_vec()),
+        }
+    }
+
+    pub fn config(&self) -> &LpConfig {
+        &self.config
+    }
+
+    pub fn get(&self, lp_pair: &LpPair) -> Option<&PairConfig> {
+        self.config.get(lp_pair)
+    }
+
+    /// Whether pairs of the LP are cached.
+    pub fn contains_lp(&self, lp_id: &LiquidityPoolId) -> bool {
+        self.lp_ids
+            .as_ref()
+            .is_none_or(|lp_ids| lp_ids.contains(lp_id))
+    }
+
+    /// Applies the config updates of cached LPs,
+    /// returning whether any config changed.
+    pub fn apply_updates(&mut self, updates: &[LpConfigUpdateEvent]) -> bool {
+        let mut is_changed = false;
+        for update in updates {
+            if !self.contains_lp(&update.lp_id) {
+                continue;
+            }
+            let lp_pair = LpPair::from((update.lp_id, update.pair));
+            if self.config.get(&lp_pair) != Some(&update.config) {
+                self.config.insert(lp_pair, update.config.clone());
+                is_changed = true;
+            }
+        }
+        is_changed
+    }
+
+    /// Applies the event if it is an [Event::LpConfigUpdate].
+    pub fn apply_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::LpConfigUpdate(updates) => self.apply_updates(updates),
+            _ => false,
+        }
+    }
+}
+
+impl LpConfigMirror {
+    /// Fetches the config of the pairs in the given LPs.
+    pub async fn start(
+        connection: &ClientConnection,
+        lp_ids: &[LiquidityPoolId],
+    ) -> Result<Self, ClientError> {
+        let config = get_lp_config(connection).await?;
+        Ok(Self::new(LpConfigCache::for_lps(config, lp_ids)))
+    }
+
+    pub fn new(cache: LpConfigCache) -> Self {
+        let (tx, rx) = watch::channel(cache);
+        Self { tx, rx }
+    }
+
+    /// Applies the event, notifying receivers if any config changed.
+    pub fn apply_event(&self, event: &Event) -> bool {
+        self.tx.send_if_modified(|cache| cache.apply_event(event))
+    }
+
+    /// Applies the events of a response, e.g. to a request setting LP params.
+    pub fn apply_response(&self, content: &ResponseContent) -> bool {
+        match content {
+            ResponseContent::Event(event) => self.apply_event(event),
+            ResponseContent::Events(events) => {
+                let mut is_changed = false;
+                for event in events {
+                    is_changed |= self.apply_event(event);
+                }
+                is_changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Replaces the configs with the current configs from the server,
+    /// e.g. after reconnecting.
+    pub async fn refresh(&self, connection: &ClientConnection) -> Result<(), ClientError> {
+        let config = get_lp_config(connection).await?;
+        self.tx.send_modify(|cache| {
+            *cache = match &cache.lp_ids {
+                Some(lp_ids) => LpConfigCache::for_lps(config, lp_ids),
+                None => LpConfigCache::new(config),
+            }
+        });
+        Ok(())
+    }
+
+    /// Returns the current configs.
+    pub fn cache(&self) -> LpConfigCache {
+        self.rx.borrow().clone()
+    }
+
+    /// Waits for the next config change.
+    pub async fn changed(&mut self) -> Result<LpConfigCache, ClientError> {
+        self.rx
+            .changed()
+            .await
+            .map_err(|_| ClientError::SubscriptionClosed)?;
+        Ok(self.rx.borrow_and_update().clone())
+    }
+
+    /// Returns a receiver that is notified of config changes,
+    /// e.g. for sharing the configs with other tasks.
+    pub fn receiver(&self) -> watch::Receiver<LpConfigCache> {
+        self.tx.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::pair::Pair;
+    use crate::mock_server::MockServer;
+    use bigdecimal::BigDecimal;
+    use ethers::prelude::U256;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn mirror_config_updates() {
+        let server = MockServer::start().await.unwrap();
+        let pair = Pair::new("ETH", "USD").unwrap();
+        let lp_pair = LpPair::from((LiquidityPoolId::new(U256::from(1)), pair));
+        let other_lp_pair = LpPair::from((LiquidityPoolId::new(U256::from(2)), pair));
+        let config = PairConfig {
+            is_active: true,
+            ..Default::default()
+        };
+        let snapshot = [lp_pair, other_lp_pair].map(|lp_pair| LpConfigUpdateEvent {
+            lp_id: lp_pair.lp_id,
+            pair: lp_pair.pair,
+            config: config.clone(),
+        });
+        server.respond_with_event(Event::LpConfigUpdate(snapshot.to_vec()));
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        // Given the config of one LP is mirrored;
+        let mut mirror = LpConfigMirror::start(&connection, &[lp_pair.lp_id])
+            .await
+            .unwrap();
+        assert_eq!(mirror.cache().get(&lp_pair), Some(&config));
+        assert_eq!(mirror.cache().config().len(), 1);
+        // When the pair's config is updated in both LPs;
+        let updated_config = PairConfig {
+            initial_margin_fraction: BigDecimal::from_str("0.05").unwrap(),
+            ..config
+        };
+        let update = |lp_pair: LpPair| LpConfigUpdateEvent {
+            lp_id: lp_pair.lp_id,
+            pair: lp_pair.pair,
+            config: updated_config.clone(),
+        };
+        let event = Event::LpConfigUpdate(vec![update(lp_pair), update(other_lp_pair)]);
+        assert!(mirror.apply_response(&ResponseContent::Events(vec![event.clone()])));
+        // Then only the mirrored LP's config should be updated.
+        let cache = mirror.changed().await.unwrap();
+        assert_eq!(cache.get(&lp_pair), Some(&updated_config));
+        assert_eq!(cache.config().len(), 1);
+        assert!(!mirror.apply_event(&event));
+    }
+}
@@ -0,0 +1,366 @@
+//! A local EVM for testing contract interactions.
+//! The mock contracts in `contracts/mocks` implement the parts of the
+//! Account, LiquidityPool, Treasury and IERC20 ABIs used by the client,
+//! and are compiled with `solc` and deployed to an `anvil` node.
+//! Both tools must be installed; tests using this harness are ignored by
+//! default and can be run with `cargo test -- --ignored`.
+use crate::environment::{
+    get_client, get_config, parse_address, set_config, Client, ConfigError, Contracts,
+    NetworkConfig,
+};
+use crate::error::ClientError;
+use crate::user::User;
+use ethers::abi::{Abi, Tokenize};
+use ethers::contract::{Contract, ContractFactory};
+use ethers::prelude::{Address, Bytes, Http, LocalWallet, Provider, Signer, U256};
+use ethers::utils::{Anvil, AnvilInstance};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use thiserror::Error;
+
+pub const MOCK_CONTRACTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/contracts/mocks");
+
+/// An anvil node with the mock contracts deployed.
+/// The node is stopped when this is dropped.
+pub struct MockChain {
+    anvil: AnvilInstance,
+    network: NetworkConfig,
+    deployer: Arc<Client>,
+    artifacts: HashMap<String, Artifact>,
+}
+
+#[derive(Debug, Clone)]
+struct Artifact {
+    abi: Abi,
+    bytecode: Bytes,
+}
+
+#[derive(Debug, Error)]
+pub enum MockChainError {
+    #[error("{0} is not installed")]
+    MissingTool(&'static str),
+    #[error("failed to compile mock contracts: {0}")]
+    Compilation(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("client error: {0}")]
+    Client(#[from] ClientError),
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+}
+
+impl MockChain {
+    /// Starts an anvil node and deploys the mock contracts to it.
+    pub async fn spawn() -> Result<Self, MockChainError> {
+        ensure_tool_installed("anvil")?;
+        let artifacts = compile_mock_contracts(Path::new(MOCK_CONTRACTS_DIR))?;
+        let anvil = Anvil::new().spawn();
+        let deployer = get_client(
+            Provider::<Http>::try_from(anvil.endpoint()).map_err(ClientError::from)?,
+            LocalWallet::from(anvil.keys()[0].clone()),
+        )
+        .await?;
+        let mut chain = Self {
+            network: NetworkConfig {
+                chain_id: anvil.chain_id(),
+                ws: String::new(),
+                subgraph: String::new(),
+                usd: Address::zero(),
+                beacon: format!("{:?}", Address::zero()),
+                account: String::new(),
+                treasury: String::new(),
+                liquidity_token_factory: format!("{:?}", Address::zero()),
+                liquidity_pool: String::new(),
+            },
+            anvil,
+            deployer,
+            artifacts,
+        };
+        let usd = chain
+            .deploy("MockERC20", ("Mock USD".to_owned(), "mUSD".to_owned()))
+            .await?;
+        let treasury = chain.deploy("MockTreasury", ()).await?;
+        let account = chain.deploy("MockAccount", treasury).await?;
+        // The scope is random, so that clients must read it from the contract.
+        let message_scope: [u8; 32] = rand::random();
+        let liquidity_pool = chain
+            .deploy("MockLiquidityPool", (treasury, message_scope))
+            .await?;
+        chain
+            .send(
+                "MockTreasury",
+                treasury,
+                "initialize",
+                (account, liquidity_pool),
+            )
+            .await?;
+        chain.network.usd = usd;
+        chain.network.treasury = format!("{treasury:?}");
+        chain.network.account = format!("{account:?}");
+        chain.network.liquidity_pool = format!("{liquidity_pool:?}");
+        Ok(chain)
+    }
+
+    /// The network config of the deployment.
+    /// The trade server URL is empty, as there is no trade server.
+    pub fn network(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    pub fn endpoint(&self) -> String {
+        self.anvil.endpoint()
+    }
+
+    /// Adds the deployment to the active config, so that it is found by
+    /// chain ID, e.g. by [Contracts::connect].
+    pub fn register_network(&self, name: &str) -> Result<(), MockChainError> {
+        let mut config = (*get_config()).clone();
+        config.insert_network(name, self.network.clone())?;
+        set_config(config);
+        Ok(())
+    }
+
+    /// Returns the funded anvil wallet at the index.
+    pub fn wallet(&self, index: usize) -> LocalWallet {
+        LocalWallet::from(self.anvil.keys()[index].clone()).with_chain_id(self.anvil.chain_id())
+    }
+
+    /// Returns a user for the funded anvil wallet at the index.
+    pub async fn user(&self, index: usize) -> Result<User, MockChainError> {
+        let wallet = self.wallet(index);
+        let provider = Provider::<Http>::try_from(self.endpoint()).map_err(ClientError::from)?;
+        let client = get_client(provider, wallet.clone()).await?;
+        Ok(User {
+            address: wallet.address(),
+            signer: wallet,
+            contracts: Contracts::from_network_config(&self.network, client)?,
+        })
+    }
+
+    /// Mints mock USD to the recipient.
+    pub async fn mint_usd(&self, to: Address, amount: U256) -> Result<(), MockChainError> {
+        self.send("MockERC20", self.network.usd, "mint", (to, amount))
+            .await
+    }
+
+    /// Opens a liquidity pool with the ID, backed by mock USD.
+    pub async fn open_pool(
+        &self,
+        pool_id: U256,
+        liquidity_token: Address,
+        owner: Address,
+    ) -> Result<(), MockChainError> {
+        let liquidity_pool = parse_address(&self.network.liquidity_pool)?;
+        self.send(
+            "MockLiquidityPool",
+            liquidity_pool,
+            "openPool",
+            (pool_id, self.network.usd, liquidity_token, owner),
+        )
+        .await
+    }
+
+    async fn deploy<T: Tokenize>(
+        &self,
+        contract_name: &str,
+        args: T,
+    ) -> Result<Address, MockChainError> {
+        let artifact = self.get_artifact(contract_name)?;
+        let factory = ContractFactory::new(
+            artifact.abi.clone(),
+            artifact.bytecode.clone(),
+            self.deployer.clone(),
+        );
+        let contract = factory
+            .deploy(args)
+            .map_err(ClientError::from)?
+            .send()
+            .await
+            .map_err(ClientError::from)?;
+        Ok(contract.address())
+    }
+
+    /// Sends a transaction from the deployer and waits for it to be mined.
+    async fn send<T: Tokenize>(
+        &self,
+        contract_name: &str,
+        address: Address,
+        method: &str,
+        args: T,
+    ) -> Result<(), MockChainError> {
+        let artifact = self.get_artifact(contract_name)?;
+        let contract = Contract::new(address, artifact.abi.clone(), self.deployer.clone());
+        let call = contract
+            .method::<_, ()>(method, args)
+            .map_err(|e| ClientError::InvalidArgument(e.to_string()))?;
+        call.send()
+            .await
+            .map_err(ClientError::from)?
+            .await
+            .map_err(ClientError::from)?;
+        Ok(())
+    }
+
+    fn get_artifact(&self, contract_name: &str) -> Result<&Artifact, MockChainError> {
+        self.artifacts
+            .get(contract_name)
+            .ok_or_else(|| MockChainError::Compilation(format!("{contract_name} not found")))
+    }
+}
+
+fn ensure_tool_installed(tool: &'static str) -> Result<(), MockChainError> {
+    match Command::new(tool).arg("--version").output() {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => Err(MockChainError::MissingTool(tool)),
+    }
+}
+
+/// Compiles the contracts in the directory, returning the artifacts
+/// by contract name.
+fn compile_mock_contracts(dir: &Path) -> Result<HashMap<String, Artifact>, MockChainError> {
+    ensure_tool_installed("solc")?;
+    let sources: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "sol"))
+        .collect();
+    let output = Command::new("solc")
+        .arg("--combined-json")
+        .arg("abi,bin")
+        .arg("--allow-paths")
+        .arg(dir)
+        .args(&sources)
+        .output()?;
+    if !output.status.success() {
+        return Err(MockChainError::Compilation(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    let compilation_error = |e: &dyn std::fmt::Display| MockChainError::Compilation(e.to_string());
+    let json: Value = serde_json::from_slice(&output.stdout).map_err(|e| compilation_error(&e))?;
+    let Some(contracts) = json["contracts"].as_object() else {
+        return Err(compilation_error(&"no contracts in solc output"));
+    };
+    let mut artifacts = HashMap::new();
+    for (key, contract) in contracts {
+        let name = key.rsplit(':').next().unwrap_or(key).to_owned();
+        // Older solc versions output the ABI as a JSON string.
+        let abi = match &contract["abi"] {
+            Value::String(abi) => serde_json::from_str(abi),
+            abi => serde_json::from_value(abi.clone()),
+        }
+        .map_err(|e| compilation_error(&e))?;
+        let bytecode = contract["bin"]
+            .as_str()
+            .unwrap_or_default()
+            .parse::<Bytes>()
+            .map_err(|e| compilation_error(&e))?;
+        artifacts.insert(name, Artifact { abi, bytecode });
+    }
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::contract_types::IERC20;
+    use crate::interface::AccountRole;
+    use crate::utils::ensure_token_approval;
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn nonce_and_allowance() {
+        let chain = MockChain::spawn().await.unwrap();
+        let user = chain.user(1).await.unwrap();
+        assert_eq!(user.get_nonce().await.unwrap(), U256::zero());
+        let target = user.contracts.account.address();
+        let amount = U256::exp10(18);
+        ensure_token_approval(
+            &user.contracts,
+            &user.signer,
+            amount,
+            chain.network().usd,
+            target,
+        )
+        .await
+        .unwrap();
+        let token = IERC20::new(chain.network().usd, user.contracts.account.client());
+        let allowance = token.allowance(user.address, target).call().await.unwrap();
+        assert_eq!(allowance, amount);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn open_and_deposit() {
+        let chain = MockChain::spawn().await.unwrap();
+        let user = chain.user(1).await.unwrap();
+        let usd = chain.network().usd;
+        let account = &user.contracts.account;
+        let amount = U256::exp10(18);
+        chain.mint_usd(user.address, amount * 2).await.unwrap();
+        ensure_token_approval(
+            &user.contracts,
+            &user.signer,
+            amount * 2,
+            usd,
+            account.address(),
+        )
+        .await
+        .unwrap();
+        // Given an account opened with a signed open message;
+        let nonce = user.get_nonce().await.unwrap();
+        let signature = user
+            .sign_role_message(U256::zero(), nonce, AccountRole::Open)
+            .await
+            .unwrap();
+        account
+            .open(
+                amount,
+                user.address,
+                user.address,
+                usd,
+                signature.to_vec().into(),
+                [0; 32],
+                true,
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        // Then the nonce should have been consumed;
+        assert_eq!(user.get_nonce().await.unwrap(), nonce + 1);
+        // When depositing with a signed deposit message;
+        let signature = user
+            .sign_role_message(U256::one(), nonce + 1, AccountRole::Deposit)
+            .await
+            .unwrap();
+        account
+            .deposit(
+                U256::one(),
+                amount,
+                user.address,
+                usd,
+                true,
+                signature.to_vec().into(),
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        // Then both deposits should have been transferred to the treasury;
+        let token = IERC20::new(usd, account.client());
+        let treasury = chain.network().treasury.parse().unwrap();
+        let balance = token.balance_of(treasury).call().await.unwrap();
+        assert_eq!(balance, amount * 2);
+        let is_owner = account
+            .does_user_have_account_role(U256::one(), user.address, AccountRole::Owner as u8)
+            .call()
+            .await
+            .unwrap();
+        assert!(is_owner);
+    }
+}
@@ -0,0 +1,296 @@
+//! An in-process trade server for offline tests.
+//! It speaks the [Request]/[Response] protocol of the [crate::interface]
+//! module: subscriptions are acknowledged automatically, while responses
+//! to all other requests are scripted by the test.
+use crate::interface::error::{ErrorCode, ResponseError};
+use crate::interface::events::Event;
+use crate::interface::{
+    MessageId, Publication, Request, RequestContent, Response, ResponseContent, SubscriptionTopic,
+};
+use futures::{SinkExt, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The scripted result of a request, or `None` for no response.
+pub type MockResponse = Option<Result<ResponseContent, ResponseError>>;
+
+type Handler = Box<dyn Fn(&RequestContent) -> MockResponse + Send + Sync>;
+
+/// A mock trade server listening on a local port for as long as it is alive.
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<MockState>,
+    task: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct MockState {
+    /// Responses for the next requests, in order.
+    responses: Mutex<VecDeque<MockResponse>>,
+    /// Responds to requests once the scripted responses run out.
+    handler: Mutex<Option<Handler>>,
+    requests: Mutex<Vec<RequestContent>>,
+    request_received: Notify,
+    clients: Mutex<HashMap<u64, MockClient>>,
+    next_id: Mutex<u64>,
+    /// Whether subscribe requests are rejected instead of acknowledged.
+    reject_subscriptions: AtomicBool,
+}
+
+struct MockClient {
+    tx: mpsc::UnboundedSender<Message>,
+    subscriptions: HashMap<MessageId, SubscriptionTopic>,
+}
+
+impl MockServer {
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(MockState::default());
+        let task = tokio::spawn(accept_clients(listener, state.clone()));
+        Ok(Self {
+            address,
+            state,
+            task,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.address)
+    }
+
+    /// Queues the response to the next request that is not a
+    /// subscription request.
+    pub fn respond(&self, response: Result<ResponseContent, ResponseError>) {
+        self.state
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(Some(response));
+    }
+
+    /// Queues an event response to the next request.
+    pub fn respond_with_event(&self, event: Event) {
+        self.respond(Ok(ResponseContent::Event(event)));
+    }
+
+    /// Queues an error response to the next request.
+    pub fn respond_with_error(&self, code: ErrorCode, message: &str) {
+        self.respond(Err(ResponseError::new(code, message)));
+    }
+
+    /// Leaves the next request unanswered, e.g. for testing timeouts.
+    pub fn ignore_next_request(&self) {
+        self.state.responses.lock().unwrap().push_back(None);
+    }
+
+    /// Sets the handler for requests without a queued response.
+    /// Without a handler, such requests are rejected.
+    pub fn set_handler(
+        &self,
+        handler: impl Fn(&RequestContent) -> MockResponse + Send + Sync + 'static,
+    ) {
+        *self.state.handler.lock().unwrap() = Some(Box::new(handler));
+    }
+
+    /// Publishes to all clients subscribed to the topic,
+    /// returning the number of subscriptions published to.
+    pub fn publish(&self, topic: &SubscriptionTopic, publication: Publication) -> usize {
+        let clients = self.state.clients.lock().unwrap();
+        let mut count = 0;
+        for client in clients.values() {
+            for (subscription_id, _) in client.subscriptions.iter().filter(|(_, t)| *t == topic) {
+                let response = Response::from_result(
+                    Ok(ResponseContent::Publication(publication.clone())),
+                    Some(subscription_id.clone()),
+                );
+                if send_response(&client.tx, &response) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Returns all requests received so far.
+    pub fn requests(&self) -> Vec<RequestContent> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Waits for a received request matching the predicate,
+    /// including requests received before calling this.
+    pub async fn wait_for_request(
+        &self,
+        predicate: impl Fn(&RequestContent) -> bool,
+        timeout: Duration,
+    ) -> Option<RequestContent> {
+        let wait = async {
+            loop {
+                let notified = self.state.request_received.notified();
+                let request_opt = self
+                    .state
+                    .requests
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|request| predicate(request))
+                    .cloned();
+                if let Some(request) = request_opt {
+                    return request;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.ok()
+    }
+
+    /// Returns the topics currently subscribed to across all clients.
+    pub fn subscriptions(&self) -> Vec<SubscriptionTopic> {
+        self.state
+            .clients
+            .lock()
+            .unwrap()
+            .values()
+            .flat_map(|client| client.subscriptions.values().cloned())
+            .collect()
+    }
+
+    /// Sets whether subscribe requests are rejected, e.g. for testing
+    /// failed resubscriptions.
+    pub fn reject_subscriptions(&self, reject: bool) {
+        self.state
+            .reject_subscriptions
+            .store(reject, Ordering::SeqCst);
+    }
+
+    /// Closes all client connections, e.g. for testing reconnection.
+    pub fn disconnect_clients(&self) {
+        self.state.clients.lock().unwrap().clear();
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        self.disconnect_clients();
+    }
+}
+
+impl MockState {
+    fn next_id(&self) -> u64 {
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id += 1;
+        *next_id
+    }
+
+    fn handle_request(&self, client_id: u64, request: Request) -> Option<Response> {
+        self.requests.lock().unwrap().push(request.content.clone());
+        self.request_received.notify_waiters();
+        let result = match &request.content {
+            RequestContent::Subscribe(_) if self.reject_subscriptions.load(Ordering::SeqCst) => {
+                Err(ResponseError::new(
+                    ErrorCode::InvalidRequest,
+                    "subscriptions rejected",
+                ))
+            }
+            RequestContent::Subscribe(topic) => {
+                let subscription_id = format!("subscription-{}", self.next_id());
+                let mut clients = self.clients.lock().unwrap();
+                let client = clients.get_mut(&client_id)?;
+                client
+                    .subscriptions
+                    .insert(subscription_id.clone(), topic.clone());
+                Ok(ResponseContent::Subscription(subscription_id))
+            }
+            RequestContent::Unsubscribe(subscription_id) => {
+                let mut clients = self.clients.lock().unwrap();
+                let client = clients.get_mut(&client_id)?;
+                client.subscriptions.remove(subscription_id);
+                Ok(ResponseContent::Subscription(subscription_id.clone()))
+            }
+            content => self.get_scripted_response(content)?,
+        };
+        Some(Response::from_result(result, request.id))
+    }
+
+    fn get_scripted_response(
+        &self,
+        content: &RequestContent,
+    ) -> Option<Result<ResponseContent, ResponseError>> {
+        if let Some(response) = self.responses.lock().unwrap().pop_front() {
+            return response;
+        }
+        match self.handler.lock().unwrap().as_ref() {
+            Some(handler) => handler(content),
+            None => Some(Err(ResponseError::new(
+                ErrorCode::InvalidRequest,
+                "no mock response",
+            ))),
+        }
+    }
+}
+
+async fn accept_clients(listener: TcpListener, state: Arc<MockState>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(handle_client(stream, state.clone()));
+    }
+}
+
+async fn handle_client(stream: TcpStream, state: Arc<MockState>) {
+    let Ok(ws_stream) = accept_async(stream).await else {
+        return;
+    };
+    let (mut write, mut read) = ws_stream.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let client_id = state.next_id();
+    state.clients.lock().unwrap().insert(
+        client_id,
+        MockClient {
+            tx: tx.clone(),
+            subscriptions: HashMap::new(),
+        },
+    );
+    // The socket is closed once all senders for the client are dropped.
+    drop(tx);
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if write.send(message).await.is_err() {
+                return;
+            }
+        }
+        _ = write.close().await;
+    });
+    while let Some(Ok(message)) = read.next().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let Ok(request) = serde_json::from_str::<Request>(&text) else {
+            continue;
+        };
+        let Some(response) = state.handle_request(client_id, request) else {
+            continue;
+        };
+        let clients = state.clients.lock().unwrap();
+        let Some(client) = clients.get(&client_id) else {
+            break;
+        };
+        send_response(&client.tx, &response);
+    }
+    state.clients.lock().unwrap().remove(&client_id);
+    writer.abort();
+}
+
+fn send_response(tx: &mpsc::UnboundedSender<Message>, response: &Response) -> bool {
+    let Ok(serialized) = serde_json::to_string(response) else {
+        return false;
+    };
+    tx.send(Message::text(serialized)).is_ok()
+}
@@ -0,0 +1,141 @@
+use crate::client_connection::ClientConnection;
+use crate::error::ClientError;
+use crate::interface::events::{ClearSystemParamEvent, Event, SetSystemParamEvent};
+use crate::interface::requests::{AdminRequest, ClearSystemParamRequest, SetSystemParamRequest};
+use crate::interface::system_params::{SystemParam, SystemParamError};
+use crate::interface::{AccountId, AccountRole, RequestContent, ResponseContent};
+use crate::user::User;
+use ethers::prelude::{Bytes, Http, LocalWallet, Middleware, Provider, Signer, U256};
+
+/// A client for protocol admins to set and clear system params.
+/// Requests are authorised by the admin's [AccountRole::ProtocolAdmin]
+/// role for their trade account.
+#[derive(Clone)]
+pub struct ProtocolAdminClient<S = LocalWallet, M = Provider<Http>> {
+    pub account_id: AccountId,
+    pub user: User<S, M>,
+    pub connection: ClientConnection,
+}
+
+impl<S: Signer + Clone + 'static, M: Middleware + 'static> ProtocolAdminClient<S, M> {
+    pub fn new(account_id: AccountId, user: User<S, M>, connection: ClientConnection) -> Self {
+        Self {
+            account_id,
+            user,
+            connection,
+        }
+    }
+
+    pub async fn set_system_param(
+        &self,
+        param: &SystemParam,
+    ) -> Result<SetSystemParamEvent, ClientError> {
+        self.set_system_param_raw(param.id(), param.encode()?).await
+    }
+
+    /// Sets a system param from its raw ID and 32 byte encoded value.
+    pub async fn set_system_param_raw(
+        &self,
+        param_id: &str,
+        param_value: Bytes,
+    ) -> Result<SetSystemParamEvent, ClientError> {
+        if param_value.len() != 32 {
+            return Err(SystemParamError::InvalidValue {
+                id: param_id.to_owned(),
+                value: param_value.to_string(),
+            }
+            .into());
+        }
+        let request = RequestContent::SetSystemParam(SetSystemParamRequest {
+            param_id: param_id.to_owned(),
+            param_value,
+            admin_request: self.get_admin_request().await?,
+        });
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::SetSystemParam(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "set system param event",
+                content,
+            )),
+        }
+    }
+
+    /// Clears a system param, reverting it to its default.
+    pub async fn clear_system_param(
+        &self,
+        param_id: &str,
+    ) -> Result<ClearSystemParamEvent, ClientError> {
+        let request = RequestContent::ClearSystemParam(ClearSystemParamRequest {
+            param_id: param_id.to_owned(),
+            admin_request: self.get_admin_request().await?,
+        });
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::ClearSystemParam(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "clear system param event",
+                content,
+            )),
+        }
+    }
+
+    async fn get_admin_request(&self) -> Result<AdminRequest, ClientError> {
+        let nonce = self.user.get_nonce().await?;
+        let signature: [u8; 65] = self
+            .user
+            .sign_role_message(
+                U256::from(self.account_id),
+                nonce,
+                AccountRole::ProtocolAdmin,
+            )
+            .await?
+            .into();
+        Ok(AdminRequest {
+            trade_account_id: self.account_id,
+            admin: self.user.address,
+            signature: signature.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::system_params::SystemParamKind;
+    use crate::mock_chain::MockChain;
+    use crate::mock_server::MockServer;
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn set_system_param() {
+        let chain = MockChain::spawn().await.unwrap();
+        let server = MockServer::start().await.unwrap();
+        server.set_handler(|request| match request {
+            RequestContent::SetSystemParam(request) => Some(Ok(ResponseContent::Event(
+                Event::SetSystemParam(request.clone()),
+            ))),
+            _ => None,
+        });
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let client = ProtocolAdminClient::new(1, chain.user(1).await.unwrap(), connection);
+        // When setting a system param;
+        let param = SystemParam::parse("feeFraction", SystemParamKind::Decimal, "0.001").unwrap();
+        let event = client.set_system_param(&param).await.unwrap();
+        // Then the value should be encoded for the contract,
+        // and the request signed by the admin.
+        assert_eq!(
+            SystemParam::decode(
+                &event.param_id,
+                SystemParamKind::Decimal,
+                &event.param_value
+            )
+            .unwrap(),
+            param
+        );
+        assert_eq!(event.admin_request.admin, client.user.address);
+        assert_eq!(event.admin_request.trade_account_id, 1);
+    }
+}
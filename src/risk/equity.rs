@@ -0,0 +1,228 @@
+//! Unrealized PnL and account equity, valued at live mark prices.
+//! Positions are valued at the price they would close at, i.e. the mark
+//! price adjusted by the pair's `symmetrical_spread_fraction`.
+use crate::interface::pair::config::PairConfig;
+use crate::interface::pair::{Pair, PairStateSnapshot};
+use crate::interface::{AccountSnapshot, LpPair, PositionSnapshot};
+use crate::risk::fees::{get_accrued_fees_from_state, PositionFees};
+use crate::risk::{get_margin, AccountMargin, PositionMargin};
+use bigdecimal::BigDecimal;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// A source of mark prices.
+pub trait PriceSource {
+    fn get_price(&self, pair: &Pair) -> Option<BigDecimal>;
+}
+
+impl PriceSource for HashMap<Pair, BigDecimal> {
+    fn get_price(&self, pair: &Pair) -> Option<BigDecimal> {
+        self.get(pair).cloned()
+    }
+}
+
+impl<P: PriceSource + ?Sized> PriceSource for &P {
+    fn get_price(&self, pair: &Pair) -> Option<BigDecimal> {
+        (**self).get_price(pair)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EquityError {
+    #[error("no price for {0}")]
+    MissingPrice(Pair),
+    #[error("no config for {0}")]
+    MissingConfig(LpPair),
+    #[error("no pair state for {0}")]
+    MissingPairState(LpPair),
+    #[error("no config for {0}, as its LP is not cached")]
+    UncachedLp(LpPair),
+}
+
+/// Values positions and accounts from a price source and market configs.
+#[derive(Debug, Clone)]
+pub struct EquityEngine<P> {
+    prices: P,
+    configs: HashMap<LpPair, PairConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionValuation {
+    pub lp_pair: LpPair,
+    pub size: BigDecimal,
+    pub mark_price: BigDecimal,
+    /// The mark price adjusted by the spread for closing the position.
+    pub exit_price: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    /// Fees accrued since the position was last updated.
+    pub fees: PositionFees,
+    pub margin: PositionMargin,
+    /// The signed notional value of the position at the mark price.
+    pub exposure: BigDecimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountEquity {
+    pub realized_equity: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    pub accrued_fees: BigDecimal,
+    /// The margin requirements, including the total equity.
+    pub margin: AccountMargin,
+    pub positions: Vec<PositionValuation>,
+    /// The signed notional exposure per LP pair.
+    pub exposure: HashMap<LpPair, BigDecimal>,
+}
+
+impl<P: PriceSource> EquityEngine<P> {
+    pub fn new(prices: P, configs: HashMap<LpPair, PairConfig>) -> Self {
+        Self { prices, configs }
+    }
+
+    pub fn prices(&self) -> &P {
+        &self.prices
+    }
+
+    pub fn prices_mut(&mut self) -> &mut P {
+        &mut self.prices
+    }
+
+    pub fn set_config(&mut self, lp_pair: LpPair, config: PairConfig) {
+        self.configs.insert(lp_pair, config);
+    }
+
+    /// Values the position. Accrued fees are included if the pair state
+    /// is provided.
+    pub fn get_position_valuation(
+        &self,
+        position: &PositionSnapshot,
+        pair_state: Option<&PairStateSnapshot>,
+    ) -> Result<PositionValuation, EquityError> {
+        let lp_pair = LpPair::from((position.lp_id, position.pair));
+        let config = self
+            .configs
+            .get(&lp_pair)
+            .ok_or(EquityError::MissingConfig(lp_pair))?;
+        let mark_price = self
+            .prices
+            .get_price(&position.pair)
+            .ok_or(EquityError::MissingPrice(position.pair))?;
+        let exit_price = get_exit_price(&position.size, &mark_price, config);
+        let unrealized_pnl = &position.size * (&exit_price - &position.entry_price);
+        let fees = pair_state
+            .map(|state| get_accrued_fees_from_state(position, state))
+            .unwrap_or_default();
+        Ok(PositionValuation {
+            lp_pair,
+            size: position.size.clone(),
+            margin: get_margin(&position.size, &mark_price, config),
+            exposure: &position.size * &mark_price,
+            mark_price,
+            exit_price,
+            unrealized_pnl,
+            fees,
+        })
+    }
+
+    /// Values the account at the current prices.
+    /// Pair states are used for accrued fees and may be empty.
+    pub fn get_account_equity(
+        &self,
+        account: &AccountSnapshot,
+        pair_states: &HashMap<LpPair, PairStateSnapshot>,
+    ) -> Result<AccountEquity, EquityError> {
+        let mut unrealized_pnl = BigDecimal::from(0);
+        let mut accrued_fees = BigDecimal::from(0);
+        let mut margin = AccountMargin::default();
+        let mut exposure: HashMap<LpPair, BigDecimal> = HashMap::new();
+        let mut positions = Vec::with_capacity(account.positions.len());
+        for position in &account.positions {
+            let lp_pair = LpPair::from((position.lp_id, position.pair));
+            let valuation = self.get_position_valuation(position, pair_states.get(&lp_pair))?;
+            unrealized_pnl += &valuation.unrealized_pnl;
+            accrued_fees += valuation.fees.total();
+            margin.add_position(&valuation.margin);
+            *exposure.entry(lp_pair).or_default() += &valuation.exposure;
+            positions.push(valuation);
+        }
+        margin.equity = &account.realized_equity + &unrealized_pnl - &accrued_fees;
+        Ok(AccountEquity {
+            realized_equity: account.realized_equity.clone(),
+            unrealized_pnl,
+            accrued_fees,
+            margin,
+            positions,
+            exposure,
+        })
+    }
+}
+
+impl AccountEquity {
+    pub fn equity(&self) -> &BigDecimal {
+        &self.margin.equity
+    }
+
+    pub fn free_margin(&self) -> BigDecimal {
+        self.margin.free_margin()
+    }
+}
+
+/// Returns the price a position of the given size would close at:
+/// longs sell below the mark price and shorts buy above it.
+pub fn get_exit_price(
+    size: &BigDecimal,
+    mark_price: &BigDecimal,
+    config: &PairConfig,
+) -> BigDecimal {
+    let one = BigDecimal::from(1);
+    if *size >= BigDecimal::default() {
+        mark_price * (one - &config.symmetrical_spread_fraction)
+    } else {
+        mark_price * (one + &config.symmetrical_spread_fraction)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::liquidity_pool::LiquidityPoolId;
+    use ethers::prelude::U256;
+    use std::str::FromStr;
+
+    #[test]
+    fn account_equity() {
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        let pair = Pair::new("ETH", "USD").unwrap();
+        let config = PairConfig {
+            symmetrical_spread_fraction: BigDecimal::from_str("0.001").unwrap(),
+            maintenance_margin_fraction: BigDecimal::from_str("0.03").unwrap(),
+            ..Default::default()
+        };
+        let engine = EquityEngine::new(
+            HashMap::from([(pair, BigDecimal::from(2000))]),
+            HashMap::from([(LpPair::from((lp_id, pair)), config)]),
+        );
+        let account = AccountSnapshot {
+            realized_equity: BigDecimal::from(100),
+            positions: vec![PositionSnapshot {
+                lp_id,
+                pair,
+                entry_price: BigDecimal::from(1900),
+                size: BigDecimal::from(1),
+                snapshot_sum_fraction_funding: BigDecimal::from(0),
+                snapshot_sum_fraction_borrow: BigDecimal::from(0),
+            }],
+            ..Default::default()
+        };
+        let equity = engine
+            .get_account_equity(&account, &HashMap::new())
+            .unwrap();
+        // The long closes at 1998 after the spread.
+        assert_eq!(equity.unrealized_pnl, BigDecimal::from(98));
+        assert_eq!(equity.equity(), &BigDecimal::from(198));
+        assert_eq!(equity.margin.maintenance_margin, BigDecimal::from(60));
+        assert_eq!(
+            equity.exposure[&LpPair::from((lp_id, pair))],
+            BigDecimal::from(2000)
+        );
+    }
+}
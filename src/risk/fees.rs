@@ -0,0 +1,254 @@
+//! Funding and borrow fee calculations.
+//! Fee sums are cumulative fractions of the position notional, tracked
+//! per market side. A position owes the difference between the current sum
+//! for its side and the sum snapshotted when it was last updated, applied to
+//! its notional value at the entry price. Positive funding is paid by the
+//! position, while negative funding is received.
+//! Rates are fractions per second, and timestamps are in unix millis.
+//! The rate models are those that [PairConfig] takes its fee params from:
+//! funding from https://github.com/gmx-io/gmx-synthetics#funding-fees and
+//! borrowing from the GLP borrow fee model.
+use crate::interface::liquidity_pool::{MarketSide, OpenInterest, TimestampedBigDecimalMarketSide};
+use crate::interface::pair::config::PairConfig;
+use crate::interface::pair::PairStateSnapshot;
+use crate::interface::PositionSnapshot;
+use bigdecimal::{BigDecimal, One, ToPrimitive};
+
+/// The fees accrued by a position since its last update.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PositionFees {
+    pub funding: BigDecimal,
+    pub borrow: BigDecimal,
+}
+
+impl PositionFees {
+    pub fn total(&self) -> BigDecimal {
+        &self.funding + &self.borrow
+    }
+}
+
+/// Returns the fees accrued by the position given the current fee sums.
+pub fn get_accrued_fees(
+    position: &PositionSnapshot,
+    sum_fraction_funding: &MarketSide<BigDecimal>,
+    sum_fraction_borrow: &MarketSide<BigDecimal>,
+) -> PositionFees {
+    let notional = (&position.size * &position.entry_price).abs();
+    let funding_delta =
+        get_side(&position.size, sum_fraction_funding) - &position.snapshot_sum_fraction_funding;
+    let borrow_delta =
+        get_side(&position.size, sum_fraction_borrow) - &position.snapshot_sum_fraction_borrow;
+    PositionFees {
+        funding: &notional * funding_delta,
+        borrow: notional * borrow_delta,
+    }
+}
+
+/// Returns the fees accrued by the position as of the pair state snapshot.
+pub fn get_accrued_fees_from_state(
+    position: &PositionSnapshot,
+    state: &PairStateSnapshot,
+) -> PositionFees {
+    get_accrued_fees(
+        position,
+        &state.sum_fraction_funding.value,
+        &state.sum_fraction_borrow.value,
+    )
+}
+
+/// Returns the fees accrued by the position up to `now_unix_millis`,
+/// projecting the fee sums of the pair state forward at the current rates.
+/// Returns `None` if the funding rates cannot be calculated.
+pub fn get_projected_fees(
+    position: &PositionSnapshot,
+    state: &PairStateSnapshot,
+    config: &PairConfig,
+    lp_value: &BigDecimal,
+    now_unix_millis: i64,
+) -> Option<PositionFees> {
+    let funding_rates = get_funding_rates(config, &state.open_interest)?;
+    let borrow_rates = get_borrow_rates(config, &state.open_interest, lp_value);
+    Some(get_accrued_fees(
+        position,
+        &project_sum_fraction(&state.sum_fraction_funding, &funding_rates, now_unix_millis),
+        &project_sum_fraction(&state.sum_fraction_borrow, &borrow_rates, now_unix_millis),
+    ))
+}
+
+/// Returns the funding rate per side, following the GMX synthetics model:
+/// the larger side pays `funding_factor * |long - short| ^ funding_exponent / (long + short)`
+/// per second, which is received by the smaller side pro rata.
+/// Returns `None` if the exponent is not a non-negative integer.
+pub fn get_funding_rates(
+    config: &PairConfig,
+    open_interest: &OpenInterest,
+) -> Option<MarketSide<BigDecimal>> {
+    let zero = BigDecimal::from(0);
+    let total = &open_interest.long + &open_interest.short;
+    if total <= zero {
+        return Some(MarketSide::default());
+    }
+    let difference = (&open_interest.long - &open_interest.short).abs();
+    let paying_rate = &config.funding_factor * pow(&difference, &config.funding_exponent)? / total;
+    let (larger, smaller) = if open_interest.long >= open_interest.short {
+        (&open_interest.long, &open_interest.short)
+    } else {
+        (&open_interest.short, &open_interest.long)
+    };
+    let receiving_rate = if *smaller > zero {
+        -(&paying_rate * larger / smaller)
+    } else {
+        zero
+    };
+    if open_interest.long >= open_interest.short {
+        Some(MarketSide {
+            long: paying_rate,
+            short: receiving_rate,
+        })
+    } else {
+        Some(MarketSide {
+            long: receiving_rate,
+            short: paying_rate,
+        })
+    }
+}
+
+/// Returns the borrow rate per side, following the GLP model:
+/// each side pays `borrow_fee_factor` scaled by its utilisation of the LP,
+/// i.e. its open interest notional over the LP value.
+pub fn get_borrow_rates(
+    config: &PairConfig,
+    open_interest: &OpenInterest,
+    lp_value: &BigDecimal,
+) -> MarketSide<BigDecimal> {
+    if *lp_value <= BigDecimal::default() {
+        return MarketSide::default();
+    }
+    MarketSide {
+        long: &config.borrow_fee_factor * &open_interest.long / lp_value,
+        short: &config.borrow_fee_factor * &open_interest.short / lp_value,
+    }
+}
+
+/// Projects the fee sums forward from their timestamp to `now_unix_millis`
+/// at the given rates per second.
+pub fn project_sum_fraction(
+    sum: &TimestampedBigDecimalMarketSide,
+    rates: &MarketSide<BigDecimal>,
+    now_unix_millis: i64,
+) -> MarketSide<BigDecimal> {
+    let elapsed_millis = (now_unix_millis - sum.timestamp).max(0);
+    let elapsed_seconds = BigDecimal::from(elapsed_millis) / BigDecimal::from(1_000);
+    MarketSide {
+        long: &sum.value.long + &rates.long * &elapsed_seconds,
+        short: &sum.value.short + &rates.short * &elapsed_seconds,
+    }
+}
+
+fn get_side<'a>(size: &BigDecimal, market_side: &'a MarketSide<BigDecimal>) -> &'a BigDecimal {
+    if *size >= BigDecimal::default() {
+        &market_side.long
+    } else {
+        &market_side.short
+    }
+}
+
+/// Raises the base to the exponent exactly, by squaring.
+/// Returns `None` if the exponent is not a non-negative integer.
+fn pow(base: &BigDecimal, exponent: &BigDecimal) -> Option<BigDecimal> {
+    if !exponent.is_integer() {
+        return None;
+    }
+    let mut exponent = exponent.to_u32()?;
+    let mut base = base.clone();
+    let mut result = BigDecimal::one();
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result *= &base;
+        }
+        base = &base * &base;
+        exponent /= 2;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::liquidity_pool::{LiquidityPoolId, TimestampedValue};
+    use crate::interface::pair::Pair;
+    use ethers::prelude::U256;
+    use std::str::FromStr;
+
+    #[test]
+    fn accrued_fees() {
+        let position = PositionSnapshot {
+            lp_id: LiquidityPoolId::new(U256::from(1)),
+            pair: Pair::new("ETH", "USD").unwrap(),
+            entry_price: BigDecimal::from(2000),
+            size: BigDecimal::from(-2),
+            snapshot_sum_fraction_funding: BigDecimal::from_str("0.001").unwrap(),
+            snapshot_sum_fraction_borrow: BigDecimal::from(0),
+        };
+        let fees = get_accrued_fees(
+            &position,
+            &MarketSide {
+                long: BigDecimal::from(1),
+                short: BigDecimal::from_str("0.003").unwrap(),
+            },
+            &MarketSide {
+                long: BigDecimal::from(1),
+                short: BigDecimal::from_str("0.0005").unwrap(),
+            },
+        );
+        // Only the short side applies, on a notional of 4000.
+        assert_eq!(fees.funding, BigDecimal::from(8));
+        assert_eq!(fees.borrow, BigDecimal::from(2));
+    }
+
+    #[test]
+    fn funding_rates() {
+        let config = PairConfig {
+            funding_factor: BigDecimal::from_str("0.0001").unwrap(),
+            funding_exponent: BigDecimal::from(1),
+            ..Default::default()
+        };
+        let open_interest = MarketSide {
+            long: BigDecimal::from(300),
+            short: BigDecimal::from(100),
+        };
+        let rates = get_funding_rates(&config, &open_interest).unwrap();
+        assert_eq!(rates.long, BigDecimal::from_str("0.00005").unwrap());
+        assert_eq!(rates.short, BigDecimal::from_str("-0.00015").unwrap());
+        // What longs pay equals what shorts receive.
+        assert_eq!(
+            &rates.long * &open_interest.long,
+            -(&rates.short * &open_interest.short)
+        );
+        let projected = project_sum_fraction(
+            &TimestampedValue::new(MarketSide::default(), 0),
+            &rates,
+            10_000,
+        );
+        assert_eq!(projected.long, BigDecimal::from_str("0.0005").unwrap());
+        // Exponents are applied exactly.
+        let config = PairConfig {
+            funding_exponent: BigDecimal::from(2),
+            ..config
+        };
+        let rates = get_funding_rates(&config, &open_interest).unwrap();
+        assert_eq!(rates.long, BigDecimal::from_str("0.01").unwrap());
+        assert_eq!(
+            pow(&BigDecimal::from_str("1.1").unwrap(), &BigDecimal::from(5)),
+            Some(BigDecimal::from_str("1.61051").unwrap())
+        );
+        // Fractional and negative exponents are not supported.
+        for exponent in ["1.5", "-1"] {
+            let config = PairConfig {
+                funding_exponent: BigDecimal::from_str(exponent).unwrap(),
+                ..config.clone()
+            };
+            assert_eq!(get_funding_rates(&config, &open_interest), None);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Margin and liquidation calculations based on the market's [PairConfig].
+//! Position sizes are in lots and are negative for short positions.
+//! Notional values and margins are in the LP currency.
+use crate::interface::order::{Order, OrderKind};
+use crate::interface::pair::config::PairConfig;
+use crate::interface::requests::TradeSize;
+use crate::interface::PositionSnapshot;
+use bigdecimal::{BigDecimal, RoundingMode};
+
+pub mod equity;
+pub mod fees;
+pub mod validation;
+
+/// The margin requirements for a single position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PositionMargin {
+    /// The absolute notional value of the position.
+    pub notional: BigDecimal,
+    pub initial_margin: BigDecimal,
+    pub maintenance_margin: BigDecimal,
+}
+
+/// The margin requirements for an account, across all positions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountMargin {
+    /// The account equity, including unrealized PnL and accrued fees.
+    pub equity: BigDecimal,
+    /// The total absolute notional value of all positions.
+    pub notional: BigDecimal,
+    pub initial_margin: BigDecimal,
+    pub maintenance_margin: BigDecimal,
+}
+
+/// Returns the initial margin fraction for a position of the given size.
+/// The fraction increases by `incremental_initial_margin_fraction` for each
+/// `incremental_position_size` step (or part thereof) that the absolute size
+/// exceeds `baseline_position_size` by.
+pub fn get_initial_margin_fraction(size: &BigDecimal, config: &PairConfig) -> BigDecimal {
+    let excess_size = size.abs() - &config.baseline_position_size;
+    let zero = BigDecimal::from(0);
+    if excess_size <= zero || config.incremental_position_size <= zero {
+        return config.initial_margin_fraction.clone();
+    }
+    let increments = (excess_size / &config.incremental_position_size)
+        .with_scale_round(0, RoundingMode::Ceiling);
+    &config.initial_margin_fraction + increments * &config.incremental_initial_margin_fraction
+}
+
+pub fn get_initial_margin(
+    size: &BigDecimal,
+    price: &BigDecimal,
+    config: &PairConfig,
+) -> BigDecimal {
+    get_notional(size, price) * get_initial_margin_fraction(size, config)
+}
+
+pub fn get_maintenance_margin(
+    size: &BigDecimal,
+    price: &BigDecimal,
+    config: &PairConfig,
+) -> BigDecimal {
+    get_notional(size, price) * &config.maintenance_margin_fraction
+}
+
+pub fn get_notional(size: &BigDecimal, price: &BigDecimal) -> BigDecimal {
+    (size * price).abs()
+}
+
+/// Returns the margin requirements of a position of the given size.
+pub fn get_margin(size: &BigDecimal, price: &BigDecimal, config: &PairConfig) -> PositionMargin {
+    PositionMargin {
+        notional: get_notional(size, price),
+        initial_margin: get_initial_margin(size, price, config),
+        maintenance_margin: get_maintenance_margin(size, price, config),
+    }
+}
+
+pub fn get_position_margin(
+    position: &PositionSnapshot,
+    price: &BigDecimal,
+    config: &PairConfig,
+) -> PositionMargin {
+    get_margin(&position.size, price, config)
+}
+
+/// Converts a trade size to lots at the given price.
+/// Returns `None` for an LPC size if the price is zero.
+pub fn get_size_in_lots(size: &TradeSize, price: &BigDecimal) -> Option<BigDecimal> {
+    match size {
+        TradeSize::Lot(lots) => Some(lots.clone()),
+        TradeSize::Lpc(_) if *price == BigDecimal::default() => None,
+        TradeSize::Lpc(amount) => Some(amount / price),
+    }
+}
+
+/// Returns the price at which the order is expected to fill, if known
+/// without a mark price.
+pub fn get_order_price(order: &Order) -> Option<&BigDecimal> {
+    match &order.kind {
+        OrderKind::Market => None,
+        OrderKind::Limit(args) => Some(&args.limit_price),
+        OrderKind::StopMarket(args) => Some(&args.trigger_price),
+        OrderKind::StopLimit(args) => Some(&args.limit_price),
+        OrderKind::LimitTrigger(args) => Some(&args.trigger_price),
+    }
+}
+
+/// Returns the position size that results from filling the order,
+/// or `None` if the order size cannot be converted to lots at the price.
+pub fn get_resulting_size(
+    current_size: &BigDecimal,
+    order: &Order,
+    price: &BigDecimal,
+) -> Option<BigDecimal> {
+    Some(current_size + get_size_in_lots(&order.size, price)?)
+}
+
+/// Returns the margin requirements of the position that results from
+/// filling the order at the given price,
+/// or `None` if the order size cannot be converted to lots at the price.
+pub fn get_order_margin(
+    current_size: &BigDecimal,
+    order: &Order,
+    price: &BigDecimal,
+    config: &PairConfig,
+) -> Option<PositionMargin> {
+    let resulting_size = get_resulting_size(current_size, order, price)?;
+    Some(get_margin(&resulting_size, price, config))
+}
+
+impl AccountMargin {
+    pub fn new(equity: BigDecimal) -> Self {
+        Self {
+            equity,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a position's margin requirements to the account.
+    pub fn add_position(&mut self, margin: &PositionMargin) {
+        self.notional += &margin.notional;
+        self.initial_margin += &margin.initial_margin;
+        self.maintenance_margin += &margin.maintenance_margin;
+    }
+
+    /// The equity available for opening or increasing positions.
+    pub fn free_margin(&self) -> BigDecimal {
+        &self.equity - &self.initial_margin
+    }
+
+    /// The ratio of maintenance margin to equity.
+    /// The account may be liquidated once this reaches one.
+    /// Returns `None` if the account has no equity.
+    pub fn margin_ratio(&self) -> Option<BigDecimal> {
+        if self.equity <= BigDecimal::default() {
+            return None;
+        }
+        Some(&self.maintenance_margin / &self.equity)
+    }
+
+    /// The ratio of total notional value to equity.
+    /// Returns `None` if the account has no equity.
+    pub fn leverage(&self) -> Option<BigDecimal> {
+        if self.equity <= BigDecimal::default() {
+            return None;
+        }
+        Some(&self.notional / &self.equity)
+    }
+
+    pub fn is_liquidatable(&self) -> bool {
+        self.equity < self.maintenance_margin
+    }
+
+    /// Whether the account meets its initial margin requirement.
+    pub fn has_sufficient_margin(&self) -> bool {
+        self.equity >= self.initial_margin
+    }
+}
+
+/// Returns the price at which the account becomes liquidatable due to
+/// this position, assuming all other positions and prices stay constant.
+/// Returns `None` if there is no such positive price.
+pub fn get_liquidation_price(
+    size: &BigDecimal,
+    mark_price: &BigDecimal,
+    account: &AccountMargin,
+    config: &PairConfig,
+) -> Option<BigDecimal> {
+    let zero = BigDecimal::from(0);
+    if *size == zero {
+        return None;
+    }
+    let other_maintenance_margin =
+        &account.maintenance_margin - get_maintenance_margin(size, mark_price, config);
+    // Solves `equity + size * (price - mark_price) = other_mm + |size| * price * mmf`.
+    let numerator = other_maintenance_margin - &account.equity + size * mark_price;
+    let denominator = size - size.abs() * &config.maintenance_margin_fraction;
+    if denominator == zero {
+        return None;
+    }
+    let price = numerator / denominator;
+    if price <= zero {
+        return None;
+    }
+    Some(price)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn get_config() -> PairConfig {
+        PairConfig {
+            initial_margin_fraction: BigDecimal::from_str("0.05").unwrap(),
+            maintenance_margin_fraction: BigDecimal::from_str("0.03").unwrap(),
+            incremental_initial_margin_fraction: BigDecimal::from_str("0.01").unwrap(),
+            baseline_position_size: BigDecimal::from(10),
+            incremental_position_size: BigDecimal::from(5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn initial_margin_fraction() {
+        let config = get_config();
+        let fraction = |size: i64| get_initial_margin_fraction(&BigDecimal::from(size), &config);
+        assert_eq!(fraction(10), BigDecimal::from_str("0.05").unwrap());
+        assert_eq!(fraction(-10), BigDecimal::from_str("0.05").unwrap());
+        assert_eq!(fraction(11), BigDecimal::from_str("0.06").unwrap());
+        assert_eq!(fraction(15), BigDecimal::from_str("0.06").unwrap());
+        assert_eq!(fraction(-16), BigDecimal::from_str("0.07").unwrap());
+    }
+
+    #[test]
+    fn size_in_lots() {
+        let price = BigDecimal::from(2000);
+        let lpc = TradeSize::Lpc(BigDecimal::from(500));
+        assert_eq!(
+            get_size_in_lots(&lpc, &price),
+            Some(BigDecimal::from_str("0.25").unwrap())
+        );
+        let lots = TradeSize::Lot(BigDecimal::from(2));
+        assert_eq!(
+            get_size_in_lots(&lots, &BigDecimal::from(0)),
+            Some(BigDecimal::from(2))
+        );
+        // An LPC size cannot be converted at a zero price.
+        assert_eq!(get_size_in_lots(&lpc, &BigDecimal::from(0)), None);
+    }
+
+    #[test]
+    fn liquidation_price() {
+        let config = get_config();
+        let size = BigDecimal::from(1);
+        let mark_price = BigDecimal::from(100);
+        let mut account = AccountMargin {
+            equity: BigDecimal::from(10),
+            ..Default::default()
+        };
+        account.add_position(&get_margin(&size, &mark_price, &config));
+        let price = get_liquidation_price(&size, &mark_price, &account, &config).unwrap();
+        // At the liquidation price, equity equals the maintenance margin.
+        let equity = &account.equity + &size * (&price - &mark_price);
+        let maintenance_margin = get_maintenance_margin(&size, &price, &config);
+        assert_eq!(equity.round(12), maintenance_margin.round(12));
+        // A short position is liquidated above the mark price.
+        let size = BigDecimal::from(-1);
+        let price = get_liquidation_price(&size, &mark_price, &account, &config).unwrap();
+        assert!(price > mark_price);
+    }
+}
@@ -0,0 +1,303 @@
+//! Pre-trade validation of orders against the market's [PairConfig], so that
+//! orders the server would reject fail before being signed and sent.
+//! Open interest is the notional value of each market side, valued at the
+//! order price.
+use crate::interface::liquidity_pool::OpenInterest;
+use crate::interface::order::Order;
+use crate::interface::pair::config::PairConfig;
+use crate::interface::LpPair;
+use crate::risk::{get_initial_margin, get_order_price, get_resulting_size, AccountMargin};
+use bigdecimal::BigDecimal;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// The market and account state that an order is validated against.
+#[derive(Debug, Clone)]
+pub struct OrderValidationContext {
+    pub config: PairConfig,
+    /// The current open interest of the pair, from its
+    /// [crate::interface::pair::PairStateSnapshot].
+    pub open_interest: OpenInterest,
+    /// The mark price, at which market orders are valued.
+    pub mark_price: BigDecimal,
+    /// The account's current position size in the pair.
+    pub position_size: BigDecimal,
+    /// The account's margin across all of its positions.
+    pub account_margin: AccountMargin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OrderRejectionReason {
+    #[error("{0} is not active")]
+    PairInactive(LpPair),
+    #[error("{0} is reduce only, and the order would increase the position")]
+    ReduceOnly(LpPair),
+    #[error("long open interest of {resulting} would exceed the maximum of {max}")]
+    MaxOpenInterestLong {
+        resulting: BigDecimal,
+        max: BigDecimal,
+    },
+    #[error("short open interest of {resulting} would exceed the maximum of {max}")]
+    MaxOpenInterestShort {
+        resulting: BigDecimal,
+        max: BigDecimal,
+    },
+    #[error("open interest difference of {resulting} would exceed the maximum of {max}")]
+    MaxOpenInterestDiff {
+        resulting: BigDecimal,
+        max: BigDecimal,
+    },
+    #[error("cannot value an LPC order size at a price of {0}")]
+    InvalidPrice(BigDecimal),
+    #[error("insufficient margin: {required} required, {available} available")]
+    InsufficientMargin {
+        required: BigDecimal,
+        available: BigDecimal,
+    },
+}
+
+/// An order that failed validation, with every reason it would be rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct OrderRejection {
+    pub reasons: Vec<OrderRejectionReason>,
+}
+
+/// Validates the order, returning all rejection reasons if it is invalid.
+pub fn validate_order(
+    order: &Order,
+    context: &OrderValidationContext,
+) -> Result<(), OrderRejection> {
+    let reasons = get_order_rejection_reasons(order, context);
+    if reasons.is_empty() {
+        return Ok(());
+    }
+    Err(OrderRejection { reasons })
+}
+
+/// Returns the reasons the order would be rejected, which is empty if the
+/// order is valid.
+/// Orders that only reduce the position are never rejected for open
+/// interest or margin.
+pub fn get_order_rejection_reasons(
+    order: &Order,
+    context: &OrderValidationContext,
+) -> Vec<OrderRejectionReason> {
+    let config = &context.config;
+    let lp_pair = LpPair::from((order.lp_id, order.pair));
+    let mut reasons = Vec::new();
+    if !config.is_active {
+        reasons.push(OrderRejectionReason::PairInactive(lp_pair));
+    }
+    let price = get_order_price(order).unwrap_or(&context.mark_price);
+    let current_size = &context.position_size;
+    let Some(resulting_size) = get_resulting_size(current_size, order, price) else {
+        reasons.push(OrderRejectionReason::InvalidPrice(price.clone()));
+        return reasons;
+    };
+    if !is_position_increase(current_size, &resulting_size) {
+        return reasons;
+    }
+    if config.is_reduce_only {
+        reasons.push(OrderRejectionReason::ReduceOnly(lp_pair));
+    }
+    reasons.extend(get_open_interest_reasons(
+        current_size,
+        &resulting_size,
+        price,
+        &context.open_interest,
+        config,
+    ));
+    let required = get_initial_margin(&resulting_size, price, config)
+        - get_initial_margin(current_size, price, config);
+    let available = context.account_margin.free_margin();
+    if required > available {
+        reasons.push(OrderRejectionReason::InsufficientMargin {
+            required,
+            available,
+        });
+    }
+    reasons
+}
+
+/// Whether the resulting position is larger than the current one,
+/// or on the other side of the market.
+pub fn is_position_increase(current_size: &BigDecimal, resulting_size: &BigDecimal) -> bool {
+    let zero = BigDecimal::from(0);
+    if *resulting_size == zero {
+        return false;
+    }
+    let is_flip = (*current_size > zero) != (*resulting_size > zero);
+    is_flip || *current_size == zero || resulting_size.abs() > current_size.abs()
+}
+
+fn get_open_interest_reasons(
+    current_size: &BigDecimal,
+    resulting_size: &BigDecimal,
+    price: &BigDecimal,
+    open_interest: &OpenInterest,
+    config: &PairConfig,
+) -> Vec<OrderRejectionReason> {
+    let zero = BigDecimal::from(0);
+    let long_size = |size: &BigDecimal| size.clone().max(zero.clone());
+    let short_size = |size: &BigDecimal| (-size).max(zero.clone());
+    let long_change = (long_size(resulting_size) - long_size(current_size)) * price;
+    let short_change = (short_size(resulting_size) - short_size(current_size)) * price;
+    let long = &open_interest.long + &long_change;
+    let short = &open_interest.short + &short_change;
+    let mut reasons = Vec::new();
+    if let Some(max) = &config.max_open_interest_long {
+        if long_change > zero && long > *max {
+            reasons.push(OrderRejectionReason::MaxOpenInterestLong {
+                resulting: long.clone(),
+                max: max.clone(),
+            });
+        }
+    }
+    if let Some(max) = &config.max_open_interest_short {
+        if short_change > zero && short > *max {
+            reasons.push(OrderRejectionReason::MaxOpenInterestShort {
+                resulting: short.clone(),
+                max: max.clone(),
+            });
+        }
+    }
+    if let Some(max) = &config.max_open_interest_diff {
+        let current_diff = (&open_interest.long - &open_interest.short).abs();
+        let diff = (long - short).abs();
+        if diff > current_diff && diff > *max {
+            reasons.push(OrderRejectionReason::MaxOpenInterestDiff {
+                resulting: diff,
+                max: max.clone(),
+            });
+        }
+    }
+    reasons
+}
+
+impl Display for OrderRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let reasons: Vec<_> = self.reasons.iter().map(ToString::to_string).collect();
+        write!(f, "{}", reasons.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::order::{LimitOrderArgs, OrderKind, TestOrderBuilder};
+    use crate::interface::requests::TradeSize;
+    use std::str::FromStr;
+
+    fn get_order(lots: i64) -> Order {
+        TestOrderBuilder::default()
+            .size(TradeSize::Lot(BigDecimal::from(lots)))
+            .build()
+    }
+
+    #[test]
+    fn order_rejection_reasons() {
+        let config = PairConfig {
+            is_active: true,
+            initial_margin_fraction: BigDecimal::from_str("0.1").unwrap(),
+            max_open_interest_long: Some(BigDecimal::from(10_000)),
+            max_open_interest_diff: Some(BigDecimal::from(5_000)),
+            ..Default::default()
+        };
+        let context = OrderValidationContext {
+            config: config.clone(),
+            open_interest: OpenInterest {
+                long: BigDecimal::from(6_000),
+                short: BigDecimal::from(2_000),
+            },
+            mark_price: BigDecimal::from(1_000),
+            position_size: BigDecimal::from(2),
+            account_margin: AccountMargin {
+                equity: BigDecimal::from(1_000),
+                initial_margin: BigDecimal::from(200),
+                ..Default::default()
+            },
+        };
+        // Given a long position of 2 with 800 free margin;
+        assert!(validate_order(&get_order(1), &context).is_ok());
+        // When the long is increased beyond the open interest caps and margin;
+        let reasons = get_order_rejection_reasons(&get_order(9), &context);
+        // Then every reason should be returned.
+        assert_eq!(
+            reasons,
+            vec![
+                OrderRejectionReason::MaxOpenInterestLong {
+                    resulting: BigDecimal::from(15_000),
+                    max: BigDecimal::from(10_000),
+                },
+                OrderRejectionReason::MaxOpenInterestDiff {
+                    resulting: BigDecimal::from(13_000),
+                    max: BigDecimal::from(5_000),
+                },
+                OrderRejectionReason::InsufficientMargin {
+                    required: BigDecimal::from(900),
+                    available: BigDecimal::from(800),
+                },
+            ]
+        );
+        // Limit orders should be valued at their limit price.
+        let order = TestOrderBuilder::default()
+            .size(TradeSize::Lot(BigDecimal::from(9)))
+            .kind(OrderKind::Limit(LimitOrderArgs {
+                limit_price: BigDecimal::from(100),
+            }))
+            .build();
+        assert!(validate_order(&order, &context).is_ok());
+        // When the pair is reduce only;
+        let context = OrderValidationContext {
+            config: PairConfig {
+                is_reduce_only: true,
+                ..config
+            },
+            ..context
+        };
+        // Then reducing should be allowed, but not increasing or flipping.
+        let lp_pair = LpPair::from((order.lp_id, order.pair));
+        assert!(validate_order(&get_order(-2), &context).is_ok());
+        assert_eq!(
+            get_order_rejection_reasons(&get_order(-3), &context),
+            vec![OrderRejectionReason::ReduceOnly(lp_pair)]
+        );
+        // When the pair is inactive;
+        let mut context = context;
+        context.config.is_active = false;
+        // Then no order should be allowed.
+        assert_eq!(
+            validate_order(&get_order(-1), &context),
+            Err(OrderRejection {
+                reasons: vec![OrderRejectionReason::PairInactive(lp_pair)]
+            })
+        );
+    }
+
+    #[test]
+    fn lpc_order_at_zero_price() {
+        let context = OrderValidationContext {
+            config: PairConfig {
+                is_active: true,
+                ..Default::default()
+            },
+            open_interest: OpenInterest::default(),
+            mark_price: BigDecimal::from(0),
+            position_size: BigDecimal::from(0),
+            account_margin: AccountMargin::new(BigDecimal::from(1_000)),
+        };
+        // Given there is no mark price for a market order;
+        let order = TestOrderBuilder::default()
+            .size(TradeSize::Lpc(BigDecimal::from(100)))
+            .build();
+        // When an LPC sized order is validated;
+        let reasons = get_order_rejection_reasons(&order, &context);
+        // Then it should be rejected, as its size in lots is unknown.
+        assert_eq!(
+            reasons,
+            vec![OrderRejectionReason::InvalidPrice(BigDecimal::from(0))]
+        );
+        // Lot sized orders can still be validated.
+        assert!(validate_order(&get_order(1), &context).is_ok());
+    }
+}
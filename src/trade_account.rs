@@ -1,24 +1,42 @@
 use crate::client_connection::ClientConnection;
-use crate::environment::DEPOSIT_TOKEN_DECIMALS;
-use crate::interface::events::{DepositEvent, Event, GrantAccountUserRoleEvent};
-use crate::interface::requests::{DepositRequest, GrantAccountUserRoleRequest, OpenAccountRequest};
-use crate::interface::{AccountId, AccountRole, RequestContent, ResponseContent};
+use crate::error::ClientError;
+use crate::interface::events::{
+    DepositEvent, Event, GrantAccountUserRoleEvent, ReplaceOrderEvent, RevokeAccountUserRoleEvent,
+    WithdrawEvent,
+};
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::order::{
+    default_expiry_unix_millis, new_order_id, now_unix_millis, Order, OrderKind,
+};
+use crate::interface::pair::Pair;
+use crate::interface::requests::{
+    CancelOrderRequest, DepositRequest, GrantAccountUserRoleRequest, OpenAccountRequest,
+    ReplaceOrderRequest, RevokeAccountUserRoleRequest, TradeSize, WithdrawRequest,
+};
+use crate::interface::{
+    AccountId, AccountRole, RequestContent, ResponseContent, SubscriptionTopic,
+};
+use crate::risk::validation::{validate_order, OrderValidationContext};
+use crate::trade_account_state::TradeAccountStateMirror;
 use crate::user::User;
-use crate::utils::ensure_token_approval;
+use crate::utils::{ensure_token_approval, get_deposit_token_amount};
 use bigdecimal::BigDecimal;
-use bigdecimal_ethers_ext::BigDecimalEthersExt;
-use ethers::prelude::{Address, U256};
-use eyre::eyre;
+use ethers::prelude::{Address, Bytes, Http, LocalWallet, Middleware, Provider, Signer, U256};
+use uuid::Uuid;
 
 #[derive(Clone)]
-pub struct TradeAccountClient {
+pub struct TradeAccountClient<S = LocalWallet, M = Provider<Http>> {
     pub id: AccountId,
-    pub user: User,
+    pub user: User<S, M>,
     pub connection: ClientConnection,
 }
 
-impl TradeAccountClient {
-    pub fn from_existing(account_id: AccountId, user: User, connection: ClientConnection) -> Self {
+impl<S: Signer + Clone + 'static, M: Middleware + 'static> TradeAccountClient<S, M> {
+    pub fn from_existing(
+        account_id: AccountId,
+        user: User<S, M>,
+        connection: ClientConnection,
+    ) -> Self {
         Self {
             id: account_id,
             user,
@@ -31,9 +49,9 @@ impl TradeAccountClient {
         token: Address,
         use_gasless: bool,
         referral_code: Option<String>,
-        user: User,
+        user: User<S, M>,
         connection: ClientConnection,
-    ) -> eyre::Result<Self> {
+    ) -> Result<Self, ClientError> {
         let request = get_open_account_request(
             &user,
             initial_deposit_amount.clone(),
@@ -48,25 +66,22 @@ impl TradeAccountClient {
             ensure_token_approval(
                 &user.contracts,
                 &user.signer,
-                initial_deposit_amount
-                    .to_ethers_u256(DEPOSIT_TOKEN_DECIMALS)
-                    .unwrap(),
+                get_deposit_token_amount(&initial_deposit_amount)?,
                 token,
                 user.contracts.account.address(),
             )
-            .await;
+            .await?;
         }
         let response = connection.send_request(request).await?;
-        let content = response.content().map_err(|e| eyre!(e))?;
-        let account_id_opt = match content {
-            ResponseContent::Event(e) => match e {
-                Event::OpenAccount(info) => Some(info.account_id),
-                _ => None,
-            },
-            _ => None,
-        };
-        let Some(account_id) = account_id_opt else {
-            return Err(eyre!("no account received"));
+        let content = response.content()?;
+        let account_id = match content {
+            ResponseContent::Event(Event::OpenAccount(info)) => info.account_id,
+            content => {
+                return Err(ClientError::unexpected_response(
+                    "open account event",
+                    content,
+                ))
+            }
         };
         Ok(Self {
             id: account_id,
@@ -80,7 +95,7 @@ impl TradeAccountClient {
         amount: BigDecimal,
         token: Address,
         use_gasless: bool,
-    ) -> eyre::Result<DepositEvent> {
+    ) -> Result<DepositEvent, ClientError> {
         let request = self
             .get_deposit_ws_request(amount.clone(), token, use_gasless)
             .await?;
@@ -90,46 +105,307 @@ impl TradeAccountClient {
             ensure_token_approval(
                 &self.user.contracts,
                 &self.user.signer,
-                amount.to_ethers_u256(DEPOSIT_TOKEN_DECIMALS).unwrap(),
+                get_deposit_token_amount(&amount)?,
                 token,
                 self.user.contracts.account.address(),
             )
-            .await;
+            .await?;
         }
         let response = self.connection.send_request(request).await?;
-        let content = response.content().map_err(|e| eyre!(e))?;
-        let deposit_event_opt = match &content {
-            ResponseContent::Event(e) => match e {
-                Event::Deposit(e) => Some(e),
-                _ => None,
-            },
-            _ => None,
-        };
-        let Some(deposit_event) = deposit_event_opt else {
-            return Err(eyre!("did not receive deposit event; {content:#?}"));
-        };
-        Ok(deposit_event.clone())
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::Deposit(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response("deposit event", content)),
+        }
+    }
+
+    /// Subscribes to the account and mirrors its state locally.
+    pub async fn mirror_state(&self) -> Result<TradeAccountStateMirror, ClientError> {
+        let subscription = self
+            .connection
+            .subscribe(SubscriptionTopic::TradeAccount(self.id))
+            .await?;
+        Ok(TradeAccountStateMirror::spawn(self.id, subscription))
+    }
+
+    /// Withdraws from the account to the recipient.
+    /// If `psm_token` is set, the withdrawn `token` is swapped to it via the hPSM.
+    pub async fn withdraw(
+        &self,
+        amount: BigDecimal,
+        token: Address,
+        recipient: Address,
+        psm_token: Option<Address>,
+    ) -> Result<WithdrawEvent, ClientError> {
+        let request = self
+            .get_withdraw_ws_request(amount, token, recipient, psm_token)
+            .await?;
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::Withdraw(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response("withdraw event", content)),
+        }
     }
 
     pub async fn grant_account_user_role(
         &self,
         user: Address,
         role: AccountRole,
-    ) -> eyre::Result<GrantAccountUserRoleEvent> {
+    ) -> Result<GrantAccountUserRoleEvent, ClientError> {
         let request = self.get_grant_role_request(user, role).await?;
         let response = self.connection.send_request(request).await?;
-        let content = response.content().map_err(|e| eyre!(e))?;
-        let grant_role_event_opt = match &content {
-            ResponseContent::Event(e) => match e {
-                Event::GrantAccountUserRole(e) => Some(e),
-                _ => None,
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::GrantAccountUserRole(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "grant role event",
+                content,
+            )),
+        }
+    }
+
+    pub async fn revoke_account_user_role(
+        &self,
+        user: Address,
+        role: AccountRole,
+    ) -> Result<RevokeAccountUserRoleEvent, ClientError> {
+        let request = self.get_revoke_role_request(user, role).await?;
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::RevokeAccountUserRole(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "revoke role event",
+                content,
+            )),
+        }
+    }
+
+    /// Returns the account user roles held by the user, as stored on chain.
+    /// Every role is checked with `doesUserHaveAccountRole`, which also
+    /// accounts for roles that are implied by others.
+    pub async fn get_account_user_roles(
+        &self,
+        user: Address,
+    ) -> Result<Vec<AccountRole>, ClientError> {
+        let account = &self.user.contracts.account;
+        let account_id = U256::from(self.id);
+        let mut roles = Vec::new();
+        for role in AccountRole::ACCOUNT_USER_ROLES {
+            let has_role = account
+                .does_user_have_account_role(account_id, user, role as u8)
+                .call()
+                .await?;
+            if has_role {
+                roles.push(role);
+            }
+        }
+        Ok(roles)
+    }
+
+    /// Returns the `roleCount` of the user from `getAccountUser`.
+    pub async fn get_account_user_role_count(&self, user: Address) -> Result<U256, ClientError> {
+        let account = &self.user.contracts.account;
+        let call = account.get_account_user(U256::from(self.id), user);
+        Ok(call.call().await?)
+    }
+
+    /// Returns the users, out of the given candidates, that currently hold
+    /// at least one role for the account, together with their roles.
+    /// The contract does not enumerate account users, so the candidates
+    /// must be known beforehand, e.g. from previous grant role events.
+    pub async fn get_account_role_holders(
+        &self,
+        candidates: &[Address],
+    ) -> Result<Vec<(Address, Vec<AccountRole>)>, ClientError> {
+        let mut holders = Vec::new();
+        for user in candidates {
+            let roles = self.get_account_user_roles(*user).await?;
+            if !roles.is_empty() {
+                holders.push((*user, roles));
+            }
+        }
+        Ok(holders)
+    }
+
+    /// Places a new order for the account.
+    /// If no expiry is given, the default order expiry is used.
+    pub async fn place_order(
+        &self,
+        lp_id: LiquidityPoolId,
+        pair: Pair,
+        size: TradeSize,
+        kind: OrderKind,
+        expiry_timestamp_unix_millis: Option<i64>,
+    ) -> Result<Order, ClientError> {
+        let order = self
+            .get_order(lp_id, pair, size, kind, expiry_timestamp_unix_millis)
+            .await?;
+        self.send_order(order).await
+    }
+
+    /// Places a new order for the account after validating it against the
+    /// market, failing with [ClientError::OrderRejected] without sending
+    /// the order if it would be rejected.
+    /// The context can be built from mirrored state with
+    /// [crate::trade_account_state::TradeAccountState::get_order_validation_context].
+    pub async fn place_validated_order(
+        &self,
+        lp_id: LiquidityPoolId,
+        pair: Pair,
+        size: TradeSize,
+        kind: OrderKind,
+        expiry_timestamp_unix_millis: Option<i64>,
+        context: &OrderValidationContext,
+    ) -> Result<Order, ClientError> {
+        let order = self.new_order(lp_id, pair, size, kind, expiry_timestamp_unix_millis);
+        validate_order(&order, context)?;
+        let order = self.sign_order(order).await?;
+        self.send_order(order).await
+    }
+
+    /// Cancels an open order, returning the cancelled order.
+    pub async fn cancel_order(&self, order_id: Uuid) -> Result<Order, ClientError> {
+        let nonce = Uuid::new_v4();
+        let request = RequestContent::CancelOrder(CancelOrderRequest {
+            account_id: self.id,
+            account_user: self.user.address,
+            order_id,
+            nonce,
+            signature: self.sign_cancel_order(order_id, nonce).await?,
+        });
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::CancelOrder(order)) => Ok(order),
+            content => Err(ClientError::unexpected_response(
+                "cancel order event",
+                content,
+            )),
+        }
+    }
+
+    /// Atomically cancels an open order and places a new one.
+    pub async fn replace_order(
+        &self,
+        order_id: Uuid,
+        lp_id: LiquidityPoolId,
+        pair: Pair,
+        size: TradeSize,
+        kind: OrderKind,
+        expiry_timestamp_unix_millis: Option<i64>,
+    ) -> Result<ReplaceOrderEvent, ClientError> {
+        let nonce = Uuid::new_v4();
+        let new_order = self
+            .get_order(lp_id, pair, size, kind, expiry_timestamp_unix_millis)
+            .await?;
+        let request = RequestContent::ReplaceOrder(ReplaceOrderRequest {
+            account_id: self.id,
+            account_user: self.user.address,
+            order_id,
+            nonce,
+            cancel_signature: self.sign_cancel_order(order_id, nonce).await?,
+            new_order,
+        });
+        let response = self.connection.send_request(request).await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::ReplaceOrder(e)) => Ok(e),
+            content => Err(ClientError::unexpected_response(
+                "replace order event",
+                content,
+            )),
+        }
+    }
+
+    async fn get_revoke_role_request(
+        &self,
+        user: Address,
+        role: AccountRole,
+    ) -> Result<RequestContent, ClientError> {
+        let nonce = self.user.get_nonce().await?;
+        let signature: [u8; 65] = self
+            .user
+            .sign_role_message(U256::from(self.id), nonce, AccountRole::Owner)
+            .await?
+            .into();
+        Ok(RequestContent::RevokeAccountUserRole(
+            RevokeAccountUserRoleRequest {
+                account_id: self.id,
+                user,
+                role,
+                account_owner: self.user.address,
+                owner_signature: signature.into(),
             },
-            _ => None,
-        };
-        let Some(grant_role_event) = grant_role_event_opt else {
-            return Err(eyre!("did not receive grant role event; {content:#?}"));
-        };
-        Ok(grant_role_event.clone())
+        ))
+    }
+
+    async fn get_order(
+        &self,
+        lp_id: LiquidityPoolId,
+        pair: Pair,
+        size: TradeSize,
+        kind: OrderKind,
+        expiry_timestamp_unix_millis: Option<i64>,
+    ) -> Result<Order, ClientError> {
+        let order = self.new_order(lp_id, pair, size, kind, expiry_timestamp_unix_millis);
+        self.sign_order(order).await
+    }
+
+    fn new_order(
+        &self,
+        lp_id: LiquidityPoolId,
+        pair: Pair,
+        size: TradeSize,
+        kind: OrderKind,
+        expiry_timestamp_unix_millis: Option<i64>,
+    ) -> Order {
+        Order {
+            id: new_order_id(),
+            account_id: self.id,
+            lp_id,
+            size,
+            pair,
+            kind,
+            status: Default::default(),
+            account_user: self.user.address,
+            signature: Default::default(),
+            nonce: Uuid::new_v4(),
+            created_timestamp_unix_millis: now_unix_millis(),
+            expiry_timestamp_unix_millis: expiry_timestamp_unix_millis
+                .unwrap_or_else(default_expiry_unix_millis),
+        }
+    }
+
+    async fn sign_order(&self, mut order: Order) -> Result<Order, ClientError> {
+        let signature: [u8; 65] = self.user.sign_order(&order).await?.into();
+        order.signature = signature.into();
+        Ok(order)
+    }
+
+    async fn send_order(&self, order: Order) -> Result<Order, ClientError> {
+        let response = self
+            .connection
+            .send_request(RequestContent::PlaceOrder(order))
+            .await?;
+        let content = response.content()?;
+        match content {
+            ResponseContent::Event(Event::PlaceOrder(order)) => Ok(order),
+            content => Err(ClientError::unexpected_response(
+                "place order event",
+                content,
+            )),
+        }
+    }
+
+    async fn sign_cancel_order(&self, order_id: Uuid, nonce: Uuid) -> Result<Bytes, ClientError> {
+        let signature: [u8; 65] = self
+            .user
+            .sign_cancel_order(self.id, order_id, nonce)
+            .await?
+            .into();
+        Ok(signature.into())
     }
 
     async fn get_deposit_ws_request(
@@ -137,11 +413,12 @@ impl TradeAccountClient {
         amount: BigDecimal,
         token: Address,
         use_gasless: bool,
-    ) -> eyre::Result<RequestContent> {
+    ) -> Result<RequestContent, ClientError> {
         let nonce = self.user.get_nonce().await?;
         let signature: [u8; 65] = self
             .user
-            .sign_role_message(U256::from(self.id), nonce, AccountRole::Deposit)?
+            .sign_role_message(U256::from(self.id), nonce, AccountRole::Deposit)
+            .await?
             .into();
         Ok(RequestContent::Deposit(DepositRequest {
             amount,
@@ -154,15 +431,40 @@ impl TradeAccountClient {
         }))
     }
 
+    async fn get_withdraw_ws_request(
+        &self,
+        amount: BigDecimal,
+        token: Address,
+        recipient: Address,
+        psm_token: Option<Address>,
+    ) -> Result<RequestContent, ClientError> {
+        let nonce = self.user.get_nonce().await?;
+        let signature: [u8; 65] = self
+            .user
+            .sign_role_message(U256::from(self.id), nonce, AccountRole::Withdraw)
+            .await?
+            .into();
+        Ok(RequestContent::Withdraw(WithdrawRequest {
+            amount,
+            account_id: self.id,
+            account_user: self.user.address,
+            token,
+            recipient,
+            signature: signature.into(),
+            psm_token,
+        }))
+    }
+
     async fn get_grant_role_request(
         &self,
         user: Address,
         role: AccountRole,
-    ) -> eyre::Result<RequestContent> {
+    ) -> Result<RequestContent, ClientError> {
         let nonce = self.user.get_nonce().await?;
         let signature: [u8; 65] = self
             .user
-            .sign_role_message(U256::from(self.id), nonce, AccountRole::Owner)?
+            .sign_role_message(U256::from(self.id), nonce, AccountRole::Owner)
+            .await?
             .into();
         Ok(RequestContent::GrantAccountUserRole(
             GrantAccountUserRoleRequest {
@@ -176,16 +478,17 @@ impl TradeAccountClient {
     }
 }
 
-async fn get_open_account_request(
-    user: &User,
+async fn get_open_account_request<S: Signer + Clone + 'static, M: Middleware + 'static>(
+    user: &User<S, M>,
     amount: BigDecimal,
     token: Address,
     use_gasless: bool,
     referral_code: Option<String>,
-) -> eyre::Result<RequestContent> {
+) -> Result<RequestContent, ClientError> {
     let nonce = user.get_nonce().await?;
     let signature: [u8; 65] = user
-        .sign_role_message(U256::from(0), nonce, AccountRole::Open)?
+        .sign_role_message(U256::from(0), nonce, AccountRole::Open)
+        .await?
         .into();
     Ok(RequestContent::OpenAccount(OpenAccountRequest {
         amount,
@@ -201,10 +504,338 @@ async fn get_open_account_request(
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::environment::CONFIG;
-    use ethers::prelude::{LocalWallet, H160};
+    use crate::environment::{get_network_config, Contracts};
+    use crate::interface::error::ErrorCode;
+    use crate::interface::liquidity_pool::OpenInterest;
+    use crate::interface::order::LimitOrderArgs;
+    use crate::interface::pair::config::PairConfig;
+    use crate::interface::signing::{
+        verify_cancel_order_signature, verify_order_signature, verify_replace_order_signature,
+    };
+    use crate::interface::LpPair;
+    use crate::mock_chain::MockChain;
+    use crate::mock_server::MockServer;
+    use crate::risk::validation::OrderRejectionReason;
+    use crate::risk::AccountMargin;
+    use ethers::prelude::{LocalWallet, SignerMiddleware, H160};
     use std::env;
     use std::str::FromStr;
+    use std::sync::Arc;
+
+    /// Returns a user that signs locally, with an RPC provider that is
+    /// never connected to.
+    fn get_offline_user() -> User {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+        let client = Arc::new(SignerMiddleware::new(provider, wallet.clone()));
+        let network = get_network_config("arbitrum-sepolia").unwrap();
+        User {
+            address: wallet.address(),
+            signer: wallet,
+            contracts: Contracts::from_network_config(&network, client).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn place_order_offline() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let account = TradeAccountClient::from_existing(1, get_offline_user(), connection);
+        server.set_handler(|request| match request {
+            RequestContent::PlaceOrder(order) => {
+                Some(Ok(ResponseContent::Event(Event::PlaceOrder(order.clone()))))
+            }
+            _ => None,
+        });
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        let pair = Pair::new("ETH", "USD").unwrap();
+        // When an order is placed;
+        let order = account
+            .place_order(
+                lp_id,
+                pair,
+                TradeSize::Lot(BigDecimal::from(1)),
+                OrderKind::Market,
+                None,
+            )
+            .await
+            .unwrap();
+        // Then the server should have received the signed order;
+        let requests = server.requests();
+        let Some(RequestContent::PlaceOrder(received)) = requests.last() else {
+            panic!("did not receive place order request; {requests:#?}");
+        };
+        assert_eq!(received.account_id, 1);
+        assert_eq!(received.account_user, account.user.address);
+        assert_eq!(received.lp_id, lp_id);
+        assert_eq!(received.pair, pair);
+        assert_eq!(received.size, TradeSize::Lot(BigDecimal::from(1)));
+        assert_eq!(received.nonce, order.nonce);
+        verify_order_signature(received).unwrap();
+        // When the server rejects an order;
+        server.respond_with_error(ErrorCode::InsufficientMargin, "insufficient margin");
+        let error = account
+            .place_order(
+                lp_id,
+                pair,
+                TradeSize::Lot(BigDecimal::from(100)),
+                OrderKind::Market,
+                None,
+            )
+            .await
+            .unwrap_err();
+        // Then the error code should be available to the caller;
+        assert_eq!(error.error_code(), Some(ErrorCode::InsufficientMargin));
+    }
+
+    #[tokio::test]
+    async fn cancel_order_offline() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let account = TradeAccountClient::from_existing(1, get_offline_user(), connection);
+        let order = account.new_order(
+            LiquidityPoolId::new(U256::from(1)),
+            Pair::new("ETH", "USD").unwrap(),
+            TradeSize::Lot(BigDecimal::from(1)),
+            OrderKind::Market,
+            None,
+        );
+        let cancelled = order.clone();
+        server.set_handler(move |request| match request {
+            RequestContent::CancelOrder(_) => Some(Ok(ResponseContent::Event(Event::CancelOrder(
+                cancelled.clone(),
+            )))),
+            _ => None,
+        });
+        // When an order is cancelled;
+        let cancelled = account.cancel_order(order.id).await.unwrap();
+        assert_eq!(cancelled.nonce, order.nonce);
+        // Then the server should have received the signed cancellation.
+        let requests = server.requests();
+        let Some(RequestContent::CancelOrder(received)) = requests.last() else {
+            panic!("did not receive cancel order request; {requests:#?}");
+        };
+        assert_eq!(received.account_id, 1);
+        assert_eq!(received.account_user, account.user.address);
+        assert_eq!(received.order_id, order.id);
+        verify_cancel_order_signature(received).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replace_order_offline() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let account = TradeAccountClient::from_existing(1, get_offline_user(), connection);
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        let pair = Pair::new("ETH", "USD").unwrap();
+        let order = account.new_order(
+            lp_id,
+            pair,
+            TradeSize::Lot(BigDecimal::from(1)),
+            OrderKind::Market,
+            None,
+        );
+        let cancelled = order.clone();
+        server.set_handler(move |request| match request {
+            RequestContent::ReplaceOrder(request) => Some(Ok(ResponseContent::Event(
+                Event::ReplaceOrder(ReplaceOrderEvent {
+                    cancelled_order: cancelled.clone(),
+                    new_order: request.new_order.clone(),
+                }),
+            ))),
+            _ => None,
+        });
+        // When an order is replaced;
+        let limit_price = BigDecimal::from(1900);
+        let event = account
+            .replace_order(
+                order.id,
+                lp_id,
+                pair,
+                TradeSize::Lot(BigDecimal::from(2)),
+                OrderKind::Limit(LimitOrderArgs {
+                    limit_price: limit_price.clone(),
+                }),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(event.cancelled_order.nonce, order.nonce);
+        // Then the server should have received the signed cancellation and new order.
+        let requests = server.requests();
+        let Some(RequestContent::ReplaceOrder(received)) = requests.last() else {
+            panic!("did not receive replace order request; {requests:#?}");
+        };
+        assert_eq!(received.account_id, 1);
+        assert_eq!(received.account_user, account.user.address);
+        assert_eq!(received.order_id, order.id);
+        assert_eq!(received.new_order.size, TradeSize::Lot(BigDecimal::from(2)));
+        assert_eq!(
+            received.new_order.kind,
+            OrderKind::Limit(LimitOrderArgs { limit_price })
+        );
+        verify_replace_order_signature(received).unwrap();
+        verify_order_signature(&received.new_order).unwrap();
+    }
+
+    #[tokio::test]
+    async fn place_validated_order_offline() {
+        let server = MockServer::start().await.unwrap();
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let account = TradeAccountClient::from_existing(1, get_offline_user(), connection);
+        let lp_id = LiquidityPoolId::new(U256::from(1));
+        let pair = Pair::new("ETH", "USD").unwrap();
+        // Given an inactive pair;
+        let context = OrderValidationContext {
+            config: PairConfig::default(),
+            open_interest: OpenInterest::default(),
+            mark_price: BigDecimal::from(2000),
+            position_size: BigDecimal::from(0),
+            account_margin: AccountMargin::new(BigDecimal::from(100)),
+        };
+        // When an order is placed;
+        let error = account
+            .place_validated_order(
+                lp_id,
+                pair,
+                TradeSize::Lot(BigDecimal::from(1)),
+                OrderKind::Market,
+                None,
+                &context,
+            )
+            .await
+            .unwrap_err();
+        // Then it should be rejected with the reason, without a request.
+        let ClientError::OrderRejected(rejection) = error else {
+            panic!("order was not rejected; {error:#?}");
+        };
+        assert_eq!(
+            rejection.reasons,
+            vec![OrderRejectionReason::PairInactive(LpPair::from((
+                lp_id, pair
+            )))]
+        );
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires anvil and solc"]
+    async fn account_user_roles() {
+        let chain = MockChain::spawn().await.unwrap();
+        let owner = chain.user(1).await.unwrap();
+        let trader = chain.wallet(2).address();
+        let contract = owner.contracts.account.clone();
+        let nonce = owner.get_nonce().await.unwrap();
+        let signature = owner
+            .sign_role_message(U256::zero(), nonce, AccountRole::Open)
+            .await
+            .unwrap();
+        contract
+            .open(
+                U256::zero(),
+                owner.address,
+                owner.address,
+                chain.network().usd,
+                signature.to_vec().into(),
+                [0; 32],
+                false,
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        // The server is mocked to echo the role requests as events,
+        // which are then applied to the chain as the server would.
+        let server = MockServer::start().await.unwrap();
+        server.set_handler(|request| match request {
+            RequestContent::GrantAccountUserRole(request) => Some(Ok(ResponseContent::Event(
+                Event::GrantAccountUserRole(request.clone()),
+            ))),
+            RequestContent::RevokeAccountUserRole(request) => Some(Ok(ResponseContent::Event(
+                Event::RevokeAccountUserRole(request.clone()),
+            ))),
+            _ => None,
+        });
+        let connection = ClientConnection::connect(&server.url()).await.unwrap();
+        let account = TradeAccountClient::from_existing(1, owner, connection);
+        // Given the trader role is granted;
+        let event = account
+            .grant_account_user_role(trader, AccountRole::Trader)
+            .await
+            .unwrap();
+        contract
+            .grant_account_user_role(
+                U256::from(event.account_id),
+                event.user,
+                event.role as u8,
+                event.account_owner,
+                event.owner_signature,
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        // Then the trader should only hold that role;
+        assert_eq!(
+            account.get_account_user_roles(trader).await.unwrap(),
+            vec![AccountRole::Trader]
+        );
+        assert_eq!(
+            account.get_account_user_role_count(trader).await.unwrap(),
+            U256::one()
+        );
+        // and the owner should hold every role, although only one is granted.
+        let owner_address = account.user.address;
+        assert_eq!(
+            account.get_account_user_roles(owner_address).await.unwrap(),
+            AccountRole::ACCOUNT_USER_ROLES.to_vec()
+        );
+        assert_eq!(
+            account
+                .get_account_user_role_count(owner_address)
+                .await
+                .unwrap(),
+            U256::one()
+        );
+        let candidates = [owner_address, trader, Address::random()];
+        let holders = account.get_account_role_holders(&candidates).await.unwrap();
+        assert_eq!(
+            holders.iter().map(|(user, _)| *user).collect::<Vec<_>>(),
+            vec![owner_address, trader]
+        );
+        // When the trader role is revoked;
+        let event = account
+            .revoke_account_user_role(trader, AccountRole::Trader)
+            .await
+            .unwrap();
+        assert_eq!(event.account_id, 1);
+        assert_eq!(event.user, trader);
+        assert_eq!(event.role, AccountRole::Trader);
+        assert_eq!(event.account_owner, owner_address);
+        contract
+            .revoke_account_user_role(
+                U256::from(event.account_id),
+                event.user,
+                event.role as u8,
+                event.account_owner,
+                event.owner_signature,
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        // Then the trader should no longer hold any role.
+        assert!(account
+            .get_account_user_roles(trader)
+            .await
+            .unwrap()
+            .is_empty());
+        let holders = account.get_account_role_holders(&candidates).await.unwrap();
+        assert_eq!(holders.len(), 1);
+    }
 
     #[tokio::test]
     async fn test_account() {
@@ -215,8 +846,9 @@ mod test {
             .unwrap();
         let rpc_url = env::var("TEST_RPC_URL").unwrap();
         let initial_deposit = BigDecimal::from(1);
-        let deposit_token = CONFIG.arbitrum_sepolia.usd;
-        let ws_url = &CONFIG.arbitrum_sepolia.ws;
+        let network = get_network_config("arbitrum-sepolia").unwrap();
+        let deposit_token = network.usd;
+        let ws_url = &network.ws;
         let user = User::connect(wallet, &rpc_url).await.unwrap();
         let connection = ClientConnection::connect(ws_url).await.unwrap();
         // Given the user has enough funds to initially deposit to an account;
@@ -249,5 +881,14 @@ mod test {
         assert_eq!(grant_role_event.user, trade_role_recipient);
         assert_eq!(grant_role_event.account_id, account.id);
         assert_eq!(grant_role_event.account_owner, account.user.address);
+        // When an account requests to revoke the trade role from that address;
+        let revoke_role_event = account
+            .revoke_account_user_role(trade_role_recipient, AccountRole::Trader)
+            .await
+            .unwrap();
+        // Then the response should be successful;
+        assert_eq!(revoke_role_event.role, AccountRole::Trader);
+        assert_eq!(revoke_role_event.user, trade_role_recipient);
+        assert_eq!(revoke_role_event.account_id, account.id);
     }
 }
@@ -0,0 +1,352 @@
+use crate::client_connection::Subscription;
+use crate::error::ClientError;
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::order::Order;
+use crate::interface::pair::{Pair, PairStateSnapshot};
+use crate::interface::{AccountId, AccountSnapshot, LpPair, PositionSnapshot, Publication};
+use crate::lp_config::LpConfigCache;
+use crate::risk::equity::{EquityEngine, EquityError, PriceSource};
+use crate::risk::validation::OrderValidationContext;
+use bigdecimal::BigDecimal;
+use futures::StreamExt;
+use std::collections::HashMap;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// A local view of a trade account, kept up to date from
+/// [Publication::TradeAccount] and [Publication::Order] publications.
+#[derive(Debug, Clone)]
+pub struct TradeAccountState {
+    snapshot: AccountSnapshot,
+    last_change: Option<TradeAccountStateChange>,
+}
+
+/// The change caused by the last publication applied to the state.
+#[derive(Debug, Clone)]
+pub enum TradeAccountStateChange {
+    /// A full account snapshot was received.
+    Snapshot,
+    /// An order for the account was placed or updated.
+    Order(Box<Order>),
+}
+
+/// Mirrors the state of a trade account in a background task for
+/// as long as this handle is alive.
+#[derive(Debug)]
+pub struct TradeAccountStateMirror {
+    rx: watch::Receiver<TradeAccountState>,
+    task: JoinHandle<()>,
+}
+
+impl TradeAccountState {
+    pub fn new(account_id: AccountId) -> Self {
+        Self {
+            snapshot: AccountSnapshot {
+                id: account_id,
+                ..Default::default()
+            },
+            last_change: None,
+        }
+    }
+
+    /// Applies the publication to the state, returning the resulting change
+    /// if the publication was relevant to the account.
+    pub fn apply(&mut self, publication: &Publication) -> Option<TradeAccountStateChange> {
+        let change = match publication {
+            Publication::TradeAccount(snapshot) if snapshot.id == self.snapshot.id => {
+                self.snapshot = snapshot.clone();
+                TradeAccountStateChange::Snapshot
+            }
+            Publication::Order(order) if order.account_id == self.snapshot.id => {
+                self.apply_order(order);
+                TradeAccountStateChange::Order(Box::new(order.clone()))
+            }
+            _ => return None,
+        };
+        self.last_change = Some(change.clone());
+        Some(change)
+    }
+
+    fn apply_order(&mut self, order: &Order) {
+        let open_orders = &mut self.snapshot.open_orders;
+        let index_opt = open_orders.iter().position(|o| o.id == order.id);
+        match (index_opt, order.status.is_open()) {
+            (Some(index), true) => open_orders[index] = order.clone(),
+            (Some(index), false) => {
+                open_orders.remove(index);
+            }
+            (None, true) => open_orders.push(order.clone()),
+            (None, false) => {}
+        }
+    }
+
+    pub fn account_id(&self) -> AccountId {
+        self.snapshot.id
+    }
+
+    pub fn snapshot(&self) -> &AccountSnapshot {
+        &self.snapshot
+    }
+
+    pub fn last_change(&self) -> Option<&TradeAccountStateChange> {
+        self.last_change.as_ref()
+    }
+
+    pub fn realized_equity(&self) -> &BigDecimal {
+        &self.snapshot.realized_equity
+    }
+
+    /// Returns the realized equity for the LP, or zero if there is none.
+    pub fn realized_equity_lp(&self, lp_id: &LiquidityPoolId) -> BigDecimal {
+        self.snapshot
+            .realized_equities_lp
+            .get(lp_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn positions(&self) -> &[PositionSnapshot] {
+        &self.snapshot.positions
+    }
+
+    pub fn position(&self, lp_id: &LiquidityPoolId, pair: &Pair) -> Option<&PositionSnapshot> {
+        self.snapshot
+            .positions
+            .iter()
+            .find(|p| p.lp_id == *lp_id && p.pair == *pair)
+    }
+
+    pub fn open_orders(&self) -> &[Order] {
+        &self.snapshot.open_orders
+    }
+
+    /// Returns the context for validating the account's orders in the
+    /// LP pair from the mirrored LP configs and pair states, valuing the
+    /// account's positions at the mark prices.
+    /// As the account margin depends on the config of every position,
+    /// the configs must be cached for the LP pair and the LPs of all the
+    /// account's positions, failing with [EquityError::UncachedLp] otherwise.
+    pub fn get_order_validation_context<P: PriceSource>(
+        &self,
+        lp_pair: &LpPair,
+        configs: &LpConfigCache,
+        pair_states: &HashMap<LpPair, PairStateSnapshot>,
+        prices: P,
+    ) -> Result<OrderValidationContext, EquityError> {
+        let position_lp_pairs = self
+            .snapshot
+            .positions
+            .iter()
+            .map(|position| LpPair::from((position.lp_id, position.pair)));
+        for required in std::iter::once(*lp_pair).chain(position_lp_pairs) {
+            if !configs.contains_lp(&required.lp_id) {
+                return Err(EquityError::UncachedLp(required));
+            }
+        }
+        let config = configs
+            .get(lp_pair)
+            .ok_or(EquityError::MissingConfig(*lp_pair))?
+            .clone();
+        let open_interest = pair_states
+            .get(lp_pair)
+            .ok_or(EquityError::MissingPairState(*lp_pair))?
+            .open_interest
+            .clone();
+        let mark_price = prices
+            .get_price(&lp_pair.pair)
+            .ok_or(EquityError::MissingPrice(lp_pair.pair))?;
+        let position_size = self
+            .position(&lp_pair.lp_id, &lp_pair.pair)
+            .map(|position| position.size.clone())
+            .unwrap_or_default();
+        let engine = EquityEngine::new(prices, configs.config().clone().into_inner());
+        let account_margin = engine
+            .get_account_equity(&self.snapshot, pair_states)?
+            .margin;
+        Ok(OrderValidationContext {
+            config,
+            open_interest,
+            mark_price,
+            position_size,
+            account_margin,
+        })
+    }
+}
+
+impl TradeAccountStateMirror {
+    /// Starts mirroring the account from a [crate::interface::SubscriptionTopic::TradeAccount]
+    /// subscription.
+    pub fn spawn(account_id: AccountId, mut subscription: Subscription) -> Self {
+        let (tx, rx) = watch::channel(TradeAccountState::new(account_id));
+        let task = tokio::spawn(async move {
+            while let Some(publication) = subscription.next().await {
+                tx.send_if_modified(|state| state.apply(&publication).is_some());
+            }
+        });
+        Self { rx, task }
+    }
+
+    /// Returns the current state of the account.
+    pub fn state(&self) -> TradeAccountState {
+        self.rx.borrow().clone()
+    }
+
+    /// Waits for the next change to the account state.
+    pub async fn changed(&mut self) -> Result<TradeAccountState, ClientError> {
+        self.rx
+            .changed()
+            .await
+            .map_err(|_| ClientError::SubscriptionClosed)?;
+        Ok(self.rx.borrow_and_update().clone())
+    }
+
+    /// Returns a receiver that is notified of account state changes,
+    /// e.g. for sharing the state with other tasks.
+    pub fn receiver(&self) -> watch::Receiver<TradeAccountState> {
+        self.rx.clone()
+    }
+}
+
+impl Drop for TradeAccountStateMirror {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::liquidity_pool::OpenInterest;
+    use crate::interface::order::{OrderFill, OrderStatus, SettlementStatus, TestOrderBuilder};
+    use crate::interface::pair::config::{LpConfig, PairConfig};
+    use crate::interface::requests::TradeSize;
+    use crate::risk::validation::{get_order_rejection_reasons, OrderRejectionReason};
+    use ethers::prelude::U256;
+    use std::str::FromStr;
+
+    fn get_order(account_id: AccountId) -> Order {
+        TestOrderBuilder::default().account_id(account_id).build()
+    }
+
+    #[test]
+    fn open_orders() {
+        let mut state = TradeAccountState::new(1);
+        state.apply(&Publication::TradeAccount(AccountSnapshot {
+            id: 1,
+            realized_equity: BigDecimal::from(100),
+            ..Default::default()
+        }));
+        assert_eq!(state.realized_equity(), &BigDecimal::from(100));
+        // Given an order is placed;
+        let mut order = get_order(1);
+        state.apply(&Publication::Order(order.clone()));
+        // Then it should be an open order;
+        assert_eq!(state.open_orders(), &[order.clone()]);
+        // When the order is filled;
+        order.status = OrderStatus::Filled(OrderFill {
+            price: BigDecimal::from(2000),
+            timestamp_unix_millis: 0,
+            settlement_status: SettlementStatus::Queued,
+        });
+        state.apply(&Publication::Order(order));
+        // Then it should no longer be an open order;
+        assert!(state.open_orders().is_empty());
+        // When an order for another account is published;
+        let change = state.apply(&Publication::Order(get_order(2)));
+        // Then it should be ignored;
+        assert!(change.is_none());
+        assert!(state.open_orders().is_empty());
+    }
+
+    #[test]
+    fn order_validation_context() {
+        let lp_pair = LpPair::from((
+            LiquidityPoolId::new(U256::from(1)),
+            Pair::new("ETH", "USD").unwrap(),
+        ));
+        let config = PairConfig {
+            is_active: true,
+            initial_margin_fraction: BigDecimal::from_str("0.1").unwrap(),
+            ..Default::default()
+        };
+        let configs = LpConfigCache::new(LpConfig::new(HashMap::from([(lp_pair, config.clone())])));
+        let open_interest = OpenInterest {
+            long: BigDecimal::from(4_000),
+            short: BigDecimal::from(1_000),
+        };
+        let pair_states = HashMap::from([(
+            lp_pair,
+            PairStateSnapshot {
+                lp_pair,
+                sum_fraction_funding: Default::default(),
+                sum_fraction_borrow: Default::default(),
+                open_interest: open_interest.clone(),
+            },
+        )]);
+        let prices = HashMap::from([(lp_pair.pair, BigDecimal::from(1_000))]);
+        let position = |lp_pair: LpPair| PositionSnapshot {
+            lp_id: lp_pair.lp_id,
+            pair: lp_pair.pair,
+            entry_price: BigDecimal::from(1_000),
+            size: BigDecimal::from(2),
+            snapshot_sum_fraction_funding: BigDecimal::from(0),
+            snapshot_sum_fraction_borrow: BigDecimal::from(0),
+        };
+        let snapshot = AccountSnapshot {
+            id: 1,
+            realized_equity: BigDecimal::from(1_000),
+            positions: vec![position(lp_pair)],
+            ..Default::default()
+        };
+        let mut state = TradeAccountState::new(1);
+        state.apply(&Publication::TradeAccount(snapshot.clone()));
+        // Given a long position of 2 at the mark price;
+        // When the validation context is built from the mirrored state;
+        let context = state
+            .get_order_validation_context(&lp_pair, &configs, &pair_states, &prices)
+            .unwrap();
+        // Then it should reflect the pair and account;
+        assert_eq!(context.config, config);
+        assert_eq!(context.open_interest, open_interest);
+        assert_eq!(context.mark_price, BigDecimal::from(1_000));
+        assert_eq!(context.position_size, BigDecimal::from(2));
+        assert_eq!(context.account_margin.equity, BigDecimal::from(1_000));
+        assert_eq!(context.account_margin.initial_margin, BigDecimal::from(200));
+        // and orders should be validated against it.
+        let order = TestOrderBuilder::default()
+            .size(TradeSize::Lot(BigDecimal::from(9)))
+            .build();
+        assert_eq!(
+            get_order_rejection_reasons(&order, &context),
+            vec![OrderRejectionReason::InsufficientMargin {
+                required: BigDecimal::from(900),
+                available: BigDecimal::from(800),
+            }]
+        );
+        // When there is no price for the pair;
+        let error = state
+            .get_order_validation_context(
+                &lp_pair,
+                &configs,
+                &pair_states,
+                HashMap::<Pair, BigDecimal>::new(),
+            )
+            .unwrap_err();
+        // Then the context cannot be built.
+        assert!(matches!(error, EquityError::MissingPrice(pair) if pair == lp_pair.pair));
+        // When the account has a position in an LP whose configs are not cached;
+        let other_lp_pair = LpPair::from((LiquidityPoolId::new(U256::from(2)), lp_pair.pair));
+        let mut positions = snapshot.positions.clone();
+        positions.push(position(other_lp_pair));
+        state.apply(&Publication::TradeAccount(AccountSnapshot {
+            positions,
+            ..snapshot
+        }));
+        let configs = LpConfigCache::for_lps(configs.config().clone(), &[lp_pair.lp_id]);
+        let error = state
+            .get_order_validation_context(&lp_pair, &configs, &pair_states, &prices)
+            .unwrap_err();
+        // Then the context cannot be built without its config.
+        assert!(matches!(error, EquityError::UncachedLp(uncached) if uncached == other_lp_pair));
+    }
+}
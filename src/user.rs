@@ -1,20 +1,29 @@
-use crate::environment::{Contracts, ACCOUNT_MESSAGE_SCOPE};
-use crate::interface::AccountRole;
-use ethers::abi;
-use ethers::abi::Token;
+use crate::environment::{get_client, Client, Contracts};
+use crate::error::ClientError;
+use crate::interface::liquidity_pool::LiquidityPoolId;
+use crate::interface::order::Order;
+use crate::interface::signing::{
+    get_cancel_order_message_hash, get_order_message_hash, get_pool_role_message_hash,
+    get_role_message_hash,
+};
+use crate::interface::{AccountId, AccountRole, LiquidityPoolRole};
+use crate::keys::{decrypt_keystore, KeystorePassword};
 use ethers::addressbook::Address;
-use ethers::prelude::{LocalWallet, Signature, Signer, U256};
-use ethers::utils::{hash_message, keccak256};
+use ethers::prelude::{Bytes, Http, LocalWallet, Middleware, Provider, Signature, Signer, U256};
+use std::path::Path;
+use uuid::Uuid;
 
+/// A user signing with `S` through a provider `M`,
+/// which default to a local wallet over HTTP.
 #[derive(Clone)]
-pub struct User {
-    pub signer: LocalWallet,
+pub struct User<S = LocalWallet, M = Provider<Http>> {
+    pub signer: S,
     pub address: Address,
-    pub contracts: Contracts,
+    pub contracts: Contracts<Client<S, M>>,
 }
 
-impl User {
-    pub async fn connect(signer: LocalWallet, rpc_url: &str) -> eyre::Result<Self> {
+impl<S: Signer + Clone + 'static> User<S> {
+    pub async fn connect(signer: S, rpc_url: &str) -> Result<Self, ClientError> {
         let address = signer.address();
         let contracts = Contracts::connect(signer.clone(), rpc_url).await?;
         Ok(Self {
@@ -23,25 +32,138 @@ impl User {
             contracts,
         })
     }
+}
 
-    pub fn sign_role_message(
+impl User {
+    /// Connects with the wallet of an encrypted JSON keystore.
+    pub async fn from_keystore(
+        path: impl AsRef<Path>,
+        password: &KeystorePassword,
+        rpc_url: &str,
+    ) -> Result<Self, ClientError> {
+        Self::connect(decrypt_keystore(path, password)?, rpc_url).await
+    }
+}
+
+impl<S: Signer + Clone + 'static, M: Middleware + 'static> User<S, M> {
+    /// Connects through any provider, e.g. over WebSocket or IPC.
+    pub async fn new(signer: S, provider: M) -> Result<Self, ClientError> {
+        let client = get_client(provider, signer.clone()).await?;
+        Ok(Self {
+            address: signer.address(),
+            signer,
+            contracts: Contracts::from_client(client).await?,
+        })
+    }
+
+    pub async fn sign_role_message(
         &self,
         account_id: U256,
         user_nonce: U256,
         role: AccountRole,
-    ) -> eyre::Result<Signature> {
-        let hash = keccak256(abi::encode(&[
-            Token::FixedBytes(ACCOUNT_MESSAGE_SCOPE.clone().into()),
-            Token::Uint(U256::from(user_nonce)),
-            Token::Uint(U256::from(account_id)),
-            Token::Uint(U256::from(role as u8)),
-        ]));
-        let signature = self.signer.sign_hash(hash_message(hash))?;
-        Ok(signature)
-    }
-
-    pub async fn get_nonce(&self) -> eyre::Result<U256> {
-        let call = &self.contracts.account.user_nonce(self.signer.address());
-        call.call().await.map_err(|e| e.into())
+    ) -> Result<Signature, ClientError> {
+        let hash = get_role_message_hash(account_id, user_nonce, role);
+        self.sign_hash(hash).await
+    }
+
+    /// Signs a pool role message with the liquidity pool nonce and the
+    /// scope of [Self::get_lp_message_scope].
+    /// The payload depends on the action, e.g. see
+    /// [crate::interface::signing::get_lp_param_payload].
+    pub async fn sign_pool_role_message(
+        &self,
+        message_scope: &[u8; 32],
+        lp_id: LiquidityPoolId,
+        lp_nonce: U256,
+        role: LiquidityPoolRole,
+        payload: &[u8],
+    ) -> Result<Signature, ClientError> {
+        let hash = get_pool_role_message_hash(message_scope, lp_id, lp_nonce, role, payload);
+        self.sign_hash(hash).await
+    }
+
+    /// Returns the scope of pool role messages from the liquidity pool contract.
+    pub async fn get_lp_message_scope(&self) -> Result<[u8; 32], ClientError> {
+        let call = &self.contracts.liquidity_pool.message_scope();
+        Ok(call.call().await?)
+    }
+
+    /// Checks the pool role signature of the user with the liquidity pool
+    /// contract for its current nonce, failing with [ClientError::Revert]
+    /// if it is invalid.
+    pub async fn validate_pool_role_signature(
+        &self,
+        lp_id: LiquidityPoolId,
+        role: LiquidityPoolRole,
+        payload: Bytes,
+        signature: Bytes,
+    ) -> Result<(), ClientError> {
+        let call = &self.contracts.liquidity_pool.validate_pool_role_signature(
+            lp_id.as_u256(),
+            self.address,
+            role as u8,
+            payload,
+            signature,
+        );
+        Ok(call.call().await?)
+    }
+
+    /// Signs the order as a trader role message with its off-chain nonce
+    /// and content.
+    pub async fn sign_order(&self, order: &Order) -> Result<Signature, ClientError> {
+        let hash = get_order_message_hash(order)?;
+        self.sign_hash(hash).await
+    }
+
+    /// Signs the cancellation of an order as a trader role message with
+    /// an off-chain nonce, as used for cancelling and replacing orders.
+    pub async fn sign_cancel_order(
+        &self,
+        account_id: AccountId,
+        order_id: Uuid,
+        nonce: Uuid,
+    ) -> Result<Signature, ClientError> {
+        let hash = get_cancel_order_message_hash(account_id, order_id, nonce);
+        self.sign_hash(hash).await
+    }
+
+    /// Returns the system params set on the Beacon contract since the block,
+    /// in order, from its `SetSystemParam` events,
+    /// e.g. for [crate::interface::system_params::SystemParamRegistry::check].
+    pub async fn get_set_system_params(
+        &self,
+        from_block: u64,
+    ) -> Result<Vec<(String, Bytes)>, ClientError> {
+        let events = self
+            .contracts
+            .beacon
+            .set_system_param_filter()
+            .from_block(from_block)
+            .query()
+            .await?;
+        Ok(events
+            .into_iter()
+            .map(|event| (event.param_id, Bytes::from(event.param_value.to_vec())))
+            .collect())
+    }
+
+    pub async fn get_nonce(&self) -> Result<U256, ClientError> {
+        let call = &self.contracts.account.user_nonce(self.address);
+        Ok(call.call().await?)
+    }
+
+    /// Returns the user nonce of the liquidity pool contract, which is
+    /// separate from the account contract nonce.
+    pub async fn get_lp_nonce(&self) -> Result<U256, ClientError> {
+        let call = &self.contracts.liquidity_pool.user_nonce(self.address);
+        Ok(call.call().await?)
+    }
+
+    /// Signs the hash as an EIP-191 message.
+    async fn sign_hash(&self, hash: [u8; 32]) -> Result<Signature, ClientError> {
+        self.signer
+            .sign_message(hash)
+            .await
+            .map_err(ClientError::signing)
     }
 }
@@ -1,24 +1,31 @@
-use crate::environment::Contracts;
+use crate::environment::{Contracts, DEPOSIT_TOKEN_DECIMALS};
+use crate::error::ClientError;
 use crate::interface::contract_types::IERC20;
+use bigdecimal::BigDecimal;
+use bigdecimal_ethers_ext::BigDecimalEthersExt;
 use ethers::addressbook::Address;
-use ethers::prelude::{LocalWallet, Signer, U256};
+use ethers::prelude::{Middleware, Signer, U256};
 
-pub async fn ensure_token_approval(
-    contracts: &Contracts,
-    signer: &LocalWallet,
+pub async fn ensure_token_approval<M: Middleware + 'static>(
+    contracts: &Contracts<M>,
+    signer: &impl Signer,
     amount: U256,
     token_address: Address,
     target: Address,
-) {
+) -> Result<(), ClientError> {
     let token = IERC20::new(token_address, contracts.account.client().clone());
-    let current_approval = token
-        .allowance(signer.address(), target)
-        .call()
-        .await
-        .unwrap();
+    let current_approval = token.allowance(signer.address(), target).call().await?;
     if current_approval >= amount {
-        return;
+        return Ok(());
     };
     let call = token.approve(target, amount);
-    call.send().await.unwrap();
+    call.send().await?;
+    Ok(())
+}
+
+/// Converts a deposit amount to the token's integer units.
+pub(crate) fn get_deposit_token_amount(amount: &BigDecimal) -> Result<U256, ClientError> {
+    amount
+        .to_ethers_u256(DEPOSIT_TOKEN_DECIMALS)
+        .ok_or_else(|| ClientError::InvalidArgument(format!("invalid deposit amount {amount}")))
 }